@@ -49,7 +49,7 @@ fn normalize(data: &mut Array2<f32>) {
 
 fn save(data: &Array2<f32>, filename: &str) {
     data.save(filename)
-        .expect(&format!("Failed to save {}", filename));
+        .unwrap_or_else(|_| panic!("Failed to save {}", filename));
 }
 
 fn main() {
@@ -0,0 +1,250 @@
+use std::{
+    env,
+    error::Error,
+    fs::{self, File},
+    io::BufWriter,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use ndarray::{concatenate, Array2, Axis};
+use noisette::{
+    gradient_grid, sample_grid, save_heightmap, HashedPerlin, HashedWorley, HeightmapFormat,
+    NodeConfig, Noise, OpenSimplex, Perlin, Simplex, Worley,
+};
+
+const USAGE: &str = "\
+Render a noise function to PNG images.
+
+Usage: noisette [OPTIONS]
+
+Options:
+  --config <FILE>       Noise graph to render (.ron or .json)
+  --generator <KIND>    Generator to render when no config is given:
+                        perlin, simplex, open_simplex, worley, hashed_perlin
+                        or hashed_worley [default: perlin]
+  --seed <SEED>         Seed for the generator [default: 0]
+  --frequency <N>       Lattice size, scale or number of points for the generator [default: 8]
+  --resolution <SIZE>   Image size as WIDTHxHEIGHT, or a single number for a square [default: 256]
+  --output <PREFIX>     Output path prefix [default: output/noise]
+  --heightmap <FILE>    Also export the samples as a heightmap, normalised for .png (16-bit) and .r16
+                        or unchanged for .r32, .pfm and .exr
+  -h, --help            Print this message
+
+Writes <PREFIX>-samples.png, <PREFIX>-gradient.png, <PREFIX>-combined.png and <PREFIX>-tiled.png.";
+
+struct Args {
+    config: Option<PathBuf>,
+    generator: String,
+    seed: Option<u64>,
+    frequency: f32,
+    resolution: (usize, usize),
+    output: PathBuf,
+    heightmap: Option<PathBuf>,
+}
+
+impl Args {
+    fn parse() -> Result<Option<Self>, String> {
+        let mut args = Args {
+            config: None,
+            generator: "perlin".to_string(),
+            seed: None,
+            frequency: 8.0,
+            resolution: (256, 256),
+            output: PathBuf::from("output/noise"),
+            heightmap: None,
+        };
+
+        let mut iter = env::args().skip(1);
+        while let Some(arg) = iter.next() {
+            if arg == "-h" || arg == "--help" {
+                return Ok(None);
+            }
+            let mut value = || {
+                iter.next()
+                    .ok_or_else(|| format!("missing value for {}", arg))
+            };
+            match arg.as_str() {
+                "--config" => args.config = Some(PathBuf::from(value()?)),
+                "--generator" => args.generator = value()?,
+                "--seed" => args.seed = Some(parse_number(&value()?, "--seed")?),
+                "--frequency" => args.frequency = parse_number(&value()?, "--frequency")?,
+                "--resolution" => args.resolution = parse_resolution(&value()?)?,
+                "--output" => args.output = PathBuf::from(value()?),
+                "--heightmap" => args.heightmap = Some(PathBuf::from(value()?)),
+                _ => return Err(format!("unrecognised argument {}", arg)),
+            }
+        }
+
+        if args.config.is_some() && args.seed.is_some() {
+            return Err(
+                "--seed cannot be used with --config, graph configs carry their own seeds"
+                    .to_string(),
+            );
+        }
+        if !args.frequency.is_finite() || args.frequency <= 0.0 {
+            return Err(format!(
+                "--frequency must be positive, got {}",
+                args.frequency
+            ));
+        }
+
+        Ok(Some(args))
+    }
+
+    fn noise(&self) -> Result<Box<dyn Noise>, Box<dyn Error>> {
+        if let Some(path) = &self.config {
+            return Ok(NodeConfig::from_file(path)?.build()?);
+        }
+
+        let seed = self.seed.unwrap_or(0);
+        let cells = self.frequency.round().max(1.0) as usize;
+        let noise: Box<dyn Noise> = match self.generator.as_str() {
+            "perlin" => Box::new(Perlin::from_seed((cells, cells), seed)),
+            "simplex" => Box::new(Simplex::from_seed(self.frequency, seed)),
+            "open_simplex" => Box::new(OpenSimplex::from_seed(self.frequency, seed)),
+            "worley" => Box::new(Worley::from_seed(cells, seed)),
+            "hashed_perlin" => Box::new(HashedPerlin::from_seed(self.frequency, seed)),
+            "hashed_worley" => Box::new(HashedWorley::from_seed(self.frequency, seed)),
+            kind => return Err(format!("unknown generator {}", kind).into()),
+        };
+        Ok(noise)
+    }
+}
+
+fn main() -> ExitCode {
+    let args = match Args::parse() {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(message) => {
+            eprintln!("error: {}\n\n{}", message, USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match run(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: &Args) -> Result<(), Box<dyn Error>> {
+    let noise = args.noise()?;
+
+    let (width, height) = args.resolution;
+    let mut samples = sample_grid(&noise, (height, width));
+    // The floating point heightmap formats keep the range that normalising for the images loses
+    let raw = samples.clone();
+    let mut magnitudes = gradient_grid(&noise, (height, width)).mapv(|g| g.norm());
+
+    let (min, max) = find_min_max(&samples);
+    println!("Samples min: {}, max: {}", min, max);
+    normalize(&mut samples);
+
+    let (min, max) = find_min_max(&magnitudes);
+    println!("Magnitudes min: {}, max: {}", min, max);
+    normalize(&mut magnitudes);
+
+    let combined = side_by_side(&samples, &magnitudes);
+    let tiled = side_by_side(&tile(&samples), &tile(&magnitudes));
+
+    if let Some(parent) = args.output.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    save(&samples, &output_path(&args.output, "samples"))?;
+    save(&magnitudes, &output_path(&args.output, "gradient"))?;
+    save(&combined, &output_path(&args.output, "combined"))?;
+    save(&tiled, &output_path(&args.output, "tiled"))?;
+
+    if let Some(path) = &args.heightmap {
+        let format = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(HeightmapFormat::from_extension);
+        let heights = match format {
+            Some(HeightmapFormat::Png16 | HeightmapFormat::R16) => &samples,
+            _ => &raw,
+        };
+        save_heightmap(heights, path)?;
+        println!("Saved {}", path.display());
+    }
+
+    Ok(())
+}
+
+fn parse_number<T: std::str::FromStr>(text: &str, name: &str) -> Result<T, String> {
+    text.parse()
+        .map_err(|_| format!("invalid value {} for {}", text, name))
+}
+
+fn parse_resolution(text: &str) -> Result<(usize, usize), String> {
+    let (width, height) = match text.split_once('x') {
+        Some((width, height)) => (
+            parse_number(width, "--resolution")?,
+            parse_number(height, "--resolution")?,
+        ),
+        None => {
+            let size = parse_number(text, "--resolution")?;
+            (size, size)
+        }
+    };
+    if width == 0 || height == 0 {
+        return Err(format!("--resolution must be non-zero, got {}", text));
+    }
+    Ok((width, height))
+}
+
+fn output_path(prefix: &Path, suffix: &str) -> PathBuf {
+    let mut name = prefix.as_os_str().to_owned();
+    name.push(format!("-{}.png", suffix));
+    PathBuf::from(name)
+}
+
+fn find_min_max(data: &Array2<f32>) -> (f32, f32) {
+    let min = data.iter().fold(f32::INFINITY, |a, &b| a.min(b));
+    let max = data.iter().fold(f32::NEG_INFINITY, |a, &b| a.max(b));
+    (min, max)
+}
+
+fn normalize(data: &mut Array2<f32>) {
+    let (min, max) = find_min_max(data);
+    let range = max - min;
+    if range > 0.0 {
+        data.mapv_inplace(|v| (v - min) / range);
+    } else {
+        data.fill(0.0);
+    }
+}
+
+fn side_by_side(left: &Array2<f32>, right: &Array2<f32>) -> Array2<f32> {
+    concatenate![Axis(1), *left, *right]
+}
+
+// Repeats an image twice in each direction.
+fn tile(data: &Array2<f32>) -> Array2<f32> {
+    let row = concatenate![Axis(1), *data, *data];
+    concatenate![Axis(0), row, row]
+}
+
+fn save(data: &Array2<f32>, path: &Path) -> Result<(), Box<dyn Error>> {
+    let (height, width) = data.dim();
+    let pixels: Vec<u8> = data
+        .iter()
+        .map(|&v| (v.clamp(0.0, 1.0) * 255.0).round() as u8)
+        .collect();
+
+    let writer = BufWriter::new(File::create(path)?);
+    let mut encoder = png::Encoder::new(writer, width as u32, height as u32);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header()?.write_image_data(&pixels)?;
+
+    println!("Saved {}", path.display());
+    Ok(())
+}
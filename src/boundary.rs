@@ -0,0 +1,31 @@
+/// How heightmap simulations treat the edges of the grid.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Boundary {
+    /// The grid ends at its edges, and anything flowing off it is lost.
+    Clamped,
+    /// Opposite edges are joined, as for tileable noise, so the result stays tileable.
+    Periodic,
+}
+
+impl Boundary {
+    // Index `i` along an axis of length `n`, or None if it falls off a clamped edge.
+    pub(crate) fn index(self, i: isize, n: usize) -> Option<usize> {
+        match self {
+            Boundary::Clamped => (0..n as isize).contains(&i).then_some(i as usize),
+            Boundary::Periodic => Some(i.rem_euclid(n as isize) as usize),
+        }
+    }
+
+    // Grid cell `(row, column)` offset by `(di, dj)`, or None if it falls off a clamped edge.
+    pub(crate) fn neighbour(
+        self,
+        (rows, columns): (usize, usize),
+        (i, j): (usize, usize),
+        (di, dj): (isize, isize),
+    ) -> Option<(usize, usize)> {
+        Some((
+            self.index(i as isize + di, rows)?,
+            self.index(j as isize + dj, columns)?,
+        ))
+    }
+}
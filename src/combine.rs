@@ -0,0 +1,516 @@
+use std::ops;
+
+use nalgebra::{Matrix2, Vector2};
+
+use crate::{
+    float::Float,
+    noise::{Noise, Noise64, NoiseAt},
+};
+
+/// Sum of two noise functions.
+pub struct Add<A = Box<dyn Noise>, B = Box<dyn Noise>> {
+    a: A,
+    b: B,
+}
+
+impl<A, B> Add<A, B> {
+    pub fn new(a: A, b: B) -> Self {
+        Self { a, b }
+    }
+
+    fn sample_generic<F: Float>(&self, x: F, y: F) -> F
+    where
+        A: NoiseAt<F>,
+        B: NoiseAt<F>,
+    {
+        self.a.sample_at(x, y) + self.b.sample_at(x, y)
+    }
+
+    fn gradient_generic<F: Float>(&self, x: F, y: F) -> Vector2<F>
+    where
+        A: NoiseAt<F>,
+        B: NoiseAt<F>,
+    {
+        self.a.gradient_at(x, y) + self.b.gradient_at(x, y)
+    }
+}
+
+impl<A: Noise, B: Noise> Noise for Add<A, B> {
+    fn sample(&self, x: f32, y: f32) -> f32 {
+        self.sample_generic(x, y)
+    }
+
+    fn gradient(&self, x: f32, y: f32) -> Vector2<f32> {
+        self.gradient_generic(x, y)
+    }
+
+    fn hessian(&self, x: f32, y: f32) -> Matrix2<f32> {
+        self.a.hessian(x, y) + self.b.hessian(x, y)
+    }
+}
+
+impl<A: Noise64, B: Noise64> Noise64 for Add<A, B> {
+    fn sample_f64(&self, x: f64, y: f64) -> f64 {
+        self.sample_generic(x, y)
+    }
+
+    fn gradient_f64(&self, x: f64, y: f64) -> Vector2<f64> {
+        self.gradient_generic(x, y)
+    }
+}
+
+/// Difference of two noise functions.
+pub struct Subtract<A = Box<dyn Noise>, B = Box<dyn Noise>> {
+    a: A,
+    b: B,
+}
+
+impl<A, B> Subtract<A, B> {
+    pub fn new(a: A, b: B) -> Self {
+        Self { a, b }
+    }
+
+    fn sample_generic<F: Float>(&self, x: F, y: F) -> F
+    where
+        A: NoiseAt<F>,
+        B: NoiseAt<F>,
+    {
+        self.a.sample_at(x, y) - self.b.sample_at(x, y)
+    }
+
+    fn gradient_generic<F: Float>(&self, x: F, y: F) -> Vector2<F>
+    where
+        A: NoiseAt<F>,
+        B: NoiseAt<F>,
+    {
+        self.a.gradient_at(x, y) - self.b.gradient_at(x, y)
+    }
+}
+
+impl<A: Noise, B: Noise> Noise for Subtract<A, B> {
+    fn sample(&self, x: f32, y: f32) -> f32 {
+        self.sample_generic(x, y)
+    }
+
+    fn gradient(&self, x: f32, y: f32) -> Vector2<f32> {
+        self.gradient_generic(x, y)
+    }
+
+    fn hessian(&self, x: f32, y: f32) -> Matrix2<f32> {
+        self.a.hessian(x, y) - self.b.hessian(x, y)
+    }
+}
+
+impl<A: Noise64, B: Noise64> Noise64 for Subtract<A, B> {
+    fn sample_f64(&self, x: f64, y: f64) -> f64 {
+        self.sample_generic(x, y)
+    }
+
+    fn gradient_f64(&self, x: f64, y: f64) -> Vector2<f64> {
+        self.gradient_generic(x, y)
+    }
+}
+
+/// Product of two noise functions.
+pub struct Multiply<A = Box<dyn Noise>, B = Box<dyn Noise>> {
+    a: A,
+    b: B,
+}
+
+impl<A, B> Multiply<A, B> {
+    pub fn new(a: A, b: B) -> Self {
+        Self { a, b }
+    }
+
+    fn sample_generic<F: Float>(&self, x: F, y: F) -> F
+    where
+        A: NoiseAt<F>,
+        B: NoiseAt<F>,
+    {
+        self.a.sample_at(x, y) * self.b.sample_at(x, y)
+    }
+
+    fn gradient_generic<F: Float>(&self, x: F, y: F) -> Vector2<F>
+    where
+        A: NoiseAt<F>,
+        B: NoiseAt<F>,
+    {
+        // Product rule: (ab)' = a'b + ab'
+        self.a.gradient_at(x, y) * self.b.sample_at(x, y)
+            + self.b.gradient_at(x, y) * self.a.sample_at(x, y)
+    }
+}
+
+impl<A: Noise, B: Noise> Noise for Multiply<A, B> {
+    fn sample(&self, x: f32, y: f32) -> f32 {
+        self.sample_generic(x, y)
+    }
+
+    fn gradient(&self, x: f32, y: f32) -> Vector2<f32> {
+        self.gradient_generic(x, y)
+    }
+
+    fn hessian(&self, x: f32, y: f32) -> Matrix2<f32> {
+        // (ab)'' = a''b + ab'' + a'b'^T + b'a'^T
+        let (a, b) = (self.a.sample(x, y), self.b.sample(x, y));
+        let (da, db) = (self.a.gradient(x, y), self.b.gradient(x, y));
+        let cross = da * db.transpose();
+        self.a.hessian(x, y) * b + self.b.hessian(x, y) * a + cross + cross.transpose()
+    }
+}
+
+impl<A: Noise64, B: Noise64> Noise64 for Multiply<A, B> {
+    fn sample_f64(&self, x: f64, y: f64) -> f64 {
+        self.sample_generic(x, y)
+    }
+
+    fn gradient_f64(&self, x: f64, y: f64) -> Vector2<f64> {
+        self.gradient_generic(x, y)
+    }
+}
+
+/// Pointwise minimum of two noise functions.
+pub struct Min<A = Box<dyn Noise>, B = Box<dyn Noise>> {
+    a: A,
+    b: B,
+}
+
+impl<A, B> Min<A, B> {
+    pub fn new(a: A, b: B) -> Self {
+        Self { a, b }
+    }
+
+    fn sample_generic<F: Float>(&self, x: F, y: F) -> F
+    where
+        A: NoiseAt<F>,
+        B: NoiseAt<F>,
+    {
+        self.a.sample_at(x, y).min(self.b.sample_at(x, y))
+    }
+
+    fn gradient_generic<F: Float>(&self, x: F, y: F) -> Vector2<F>
+    where
+        A: NoiseAt<F>,
+        B: NoiseAt<F>,
+    {
+        if self.a.sample_at(x, y) <= self.b.sample_at(x, y) {
+            self.a.gradient_at(x, y)
+        } else {
+            self.b.gradient_at(x, y)
+        }
+    }
+}
+
+impl<A: Noise, B: Noise> Noise for Min<A, B> {
+    fn sample(&self, x: f32, y: f32) -> f32 {
+        self.sample_generic(x, y)
+    }
+
+    fn gradient(&self, x: f32, y: f32) -> Vector2<f32> {
+        self.gradient_generic(x, y)
+    }
+}
+
+impl<A: Noise64, B: Noise64> Noise64 for Min<A, B> {
+    fn sample_f64(&self, x: f64, y: f64) -> f64 {
+        self.sample_generic(x, y)
+    }
+
+    fn gradient_f64(&self, x: f64, y: f64) -> Vector2<f64> {
+        self.gradient_generic(x, y)
+    }
+}
+
+/// Pointwise maximum of two noise functions.
+pub struct Max<A = Box<dyn Noise>, B = Box<dyn Noise>> {
+    a: A,
+    b: B,
+}
+
+impl<A, B> Max<A, B> {
+    pub fn new(a: A, b: B) -> Self {
+        Self { a, b }
+    }
+
+    fn sample_generic<F: Float>(&self, x: F, y: F) -> F
+    where
+        A: NoiseAt<F>,
+        B: NoiseAt<F>,
+    {
+        self.a.sample_at(x, y).max(self.b.sample_at(x, y))
+    }
+
+    fn gradient_generic<F: Float>(&self, x: F, y: F) -> Vector2<F>
+    where
+        A: NoiseAt<F>,
+        B: NoiseAt<F>,
+    {
+        if self.a.sample_at(x, y) >= self.b.sample_at(x, y) {
+            self.a.gradient_at(x, y)
+        } else {
+            self.b.gradient_at(x, y)
+        }
+    }
+}
+
+impl<A: Noise, B: Noise> Noise for Max<A, B> {
+    fn sample(&self, x: f32, y: f32) -> f32 {
+        self.sample_generic(x, y)
+    }
+
+    fn gradient(&self, x: f32, y: f32) -> Vector2<f32> {
+        self.gradient_generic(x, y)
+    }
+}
+
+impl<A: Noise64, B: Noise64> Noise64 for Max<A, B> {
+    fn sample_f64(&self, x: f64, y: f64) -> f64 {
+        self.sample_generic(x, y)
+    }
+
+    fn gradient_f64(&self, x: f64, y: f64) -> Vector2<f64> {
+        self.gradient_generic(x, y)
+    }
+}
+
+/// Linear interpolation between two noise functions, `a + t * (b - a)`,
+/// where `t` is the value of the control noise.
+///
+/// The control value is used as-is, so it should be remapped into [0, 1] beforehand.
+pub struct Blend<A = Box<dyn Noise>, B = Box<dyn Noise>, C = Box<dyn Noise>> {
+    a: A,
+    b: B,
+    control: C,
+}
+
+impl<A, B, C> Blend<A, B, C> {
+    pub fn new(a: A, b: B, control: C) -> Self {
+        Self { a, b, control }
+    }
+
+    fn sample_generic<F: Float>(&self, x: F, y: F) -> F
+    where
+        A: NoiseAt<F>,
+        B: NoiseAt<F>,
+        C: NoiseAt<F>,
+    {
+        let a = self.a.sample_at(x, y);
+        let b = self.b.sample_at(x, y);
+        let t = self.control.sample_at(x, y);
+        a + t * (b - a)
+    }
+
+    fn gradient_generic<F: Float>(&self, x: F, y: F) -> Vector2<F>
+    where
+        A: NoiseAt<F>,
+        B: NoiseAt<F>,
+        C: NoiseAt<F>,
+    {
+        let a = self.a.sample_at(x, y);
+        let b = self.b.sample_at(x, y);
+        let t = self.control.sample_at(x, y);
+        let ga = self.a.gradient_at(x, y);
+        let gb = self.b.gradient_at(x, y);
+        let gt = self.control.gradient_at(x, y);
+        ga + (gb - ga) * t + gt * (b - a)
+    }
+}
+
+impl<A: Noise, B: Noise, C: Noise> Noise for Blend<A, B, C> {
+    fn sample(&self, x: f32, y: f32) -> f32 {
+        self.sample_generic(x, y)
+    }
+
+    fn gradient(&self, x: f32, y: f32) -> Vector2<f32> {
+        self.gradient_generic(x, y)
+    }
+}
+
+impl<A: Noise64, B: Noise64, C: Noise64> Noise64 for Blend<A, B, C> {
+    fn sample_f64(&self, x: f64, y: f64) -> f64 {
+        self.sample_generic(x, y)
+    }
+
+    fn gradient_f64(&self, x: f64, y: f64) -> Vector2<f64> {
+        self.gradient_generic(x, y)
+    }
+}
+
+/// Chooses between two noise functions depending on whether the control noise
+/// is below (`a`) or above (`b`) a threshold.
+///
+/// Within `falloff` of the threshold the two are blended with a smoothstep curve.
+/// A falloff of zero gives a hard edge.
+pub struct Select<A = Box<dyn Noise>, B = Box<dyn Noise>, C = Box<dyn Noise>> {
+    a: A,
+    b: B,
+    control: C,
+    threshold: f32,
+    falloff: f32,
+}
+
+impl<A, B, C> Select<A, B, C> {
+    pub fn new(a: A, b: B, control: C, threshold: f32, falloff: f32) -> Self {
+        debug_assert!(falloff >= 0.0);
+
+        Self {
+            a,
+            b,
+            control,
+            threshold,
+            falloff,
+        }
+    }
+
+    // Blend weight of `b` and its derivative with respect to the control value.
+    fn weight<F: Float>(&self, c: F) -> (F, F) {
+        let (zero, one) = (F::zero(), F::one());
+        let threshold = F::cast(self.threshold as f64);
+        if self.falloff <= 0.0 {
+            return (if c < threshold { zero } else { one }, zero);
+        }
+
+        let falloff = F::cast(self.falloff as f64);
+        let lower = threshold - falloff;
+        let width = F::cast(2.0) * falloff;
+        let t = (c - lower) / width;
+        if t <= zero {
+            (zero, zero)
+        } else if t >= one {
+            (one, zero)
+        } else {
+            (
+                t * t * (F::cast(3.0) - F::cast(2.0) * t),
+                F::cast(6.0) * t * (one - t) / width,
+            )
+        }
+    }
+
+    fn sample_generic<F: Float>(&self, x: F, y: F) -> F
+    where
+        A: NoiseAt<F>,
+        B: NoiseAt<F>,
+        C: NoiseAt<F>,
+    {
+        let (w, _) = self.weight(self.control.sample_at(x, y));
+        if w <= F::zero() {
+            return self.a.sample_at(x, y);
+        }
+        if w >= F::one() {
+            return self.b.sample_at(x, y);
+        }
+
+        let a = self.a.sample_at(x, y);
+        let b = self.b.sample_at(x, y);
+        a + w * (b - a)
+    }
+
+    fn gradient_generic<F: Float>(&self, x: F, y: F) -> Vector2<F>
+    where
+        A: NoiseAt<F>,
+        B: NoiseAt<F>,
+        C: NoiseAt<F>,
+    {
+        let (w, dw) = self.weight(self.control.sample_at(x, y));
+        if w <= F::zero() {
+            return self.a.gradient_at(x, y);
+        }
+        if w >= F::one() {
+            return self.b.gradient_at(x, y);
+        }
+
+        let a = self.a.sample_at(x, y);
+        let b = self.b.sample_at(x, y);
+        let ga = self.a.gradient_at(x, y);
+        let gb = self.b.gradient_at(x, y);
+        let gc = self.control.gradient_at(x, y);
+        ga + (gb - ga) * w + gc * (dw * (b - a))
+    }
+}
+
+impl<A: Noise, B: Noise, C: Noise> Noise for Select<A, B, C> {
+    fn sample(&self, x: f32, y: f32) -> f32 {
+        self.sample_generic(x, y)
+    }
+
+    fn gradient(&self, x: f32, y: f32) -> Vector2<f32> {
+        self.gradient_generic(x, y)
+    }
+}
+
+impl<A: Noise64, B: Noise64, C: Noise64> Noise64 for Select<A, B, C> {
+    fn sample_f64(&self, x: f64, y: f64) -> f64 {
+        self.sample_generic(x, y)
+    }
+
+    fn gradient_f64(&self, x: f64, y: f64) -> Vector2<f64> {
+        self.gradient_generic(x, y)
+    }
+}
+
+impl ops::Add for Box<dyn Noise> {
+    type Output = Box<dyn Noise>;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Box::new(Add::new(self, rhs))
+    }
+}
+
+impl ops::Sub for Box<dyn Noise> {
+    type Output = Box<dyn Noise>;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Box::new(Subtract::new(self, rhs))
+    }
+}
+
+impl ops::Mul for Box<dyn Noise> {
+    type Output = Box<dyn Noise>;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        Box::new(Multiply::new(self, rhs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        noise::{assert_f64_matches, assert_gradient_matches, assert_hessian_matches},
+        Perlin,
+    };
+
+    fn perlin(seed: u64) -> Box<dyn Noise> {
+        Box::new(Perlin::from_seed((4, 4), seed))
+    }
+
+    #[test]
+    fn gradients_match_finite_differences() {
+        assert_gradient_matches(&Add::new(perlin(1), perlin(2)));
+        assert_gradient_matches(&Subtract::new(perlin(1), perlin(2)));
+        assert_gradient_matches(&Multiply::new(perlin(1), perlin(2)));
+        assert_gradient_matches(&Min::new(perlin(1), perlin(2)));
+        assert_gradient_matches(&Max::new(perlin(1), perlin(2)));
+        assert_gradient_matches(&Blend::new(perlin(1), perlin(2), perlin(3)));
+        assert_gradient_matches(&Select::new(perlin(1), perlin(2), perlin(3), 0.1, 0.2));
+    }
+
+    #[test]
+    fn hessians_match_finite_differences() {
+        assert_hessian_matches(&Add::new(perlin(1), perlin(2)));
+        assert_hessian_matches(&Subtract::new(perlin(1), perlin(2)));
+        assert_hessian_matches(&Multiply::new(perlin(1), perlin(2)));
+    }
+
+    #[test]
+    fn f64_matches_f32() {
+        let perlin = |seed| Perlin::from_seed((4, 4), seed);
+        assert_f64_matches(&Add::new(perlin(1), perlin(2)));
+        assert_f64_matches(&Subtract::new(perlin(1), perlin(2)));
+        assert_f64_matches(&Multiply::new(perlin(1), perlin(2)));
+        assert_f64_matches(&Min::new(perlin(1), perlin(2)));
+        assert_f64_matches(&Max::new(perlin(1), perlin(2)));
+        assert_f64_matches(&Blend::new(perlin(1), perlin(2), perlin(3)));
+        assert_f64_matches(&Select::new(perlin(1), perlin(2), perlin(3), 0.1, 0.2));
+    }
+}
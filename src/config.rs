@@ -0,0 +1,604 @@
+//! Declarative noise graphs.
+//!
+//! A graph is a tree of nodes, each tagged with its `kind`, which can be written in RON or JSON.
+//! For example, in RON:
+//!
+//! ```ron
+//! (
+//!     kind: "stack",
+//!     gradient_function: Exp(scale: 0.5),
+//!     layers: [
+//!         (weight: 1.0, noise: (kind: "perlin", shape: (5, 5), seed: 1)),
+//!         (weight: 0.5, noise: (kind: "scale_bias", scale: 2.0, bias: 0.0,
+//!             source: (kind: "simplex", scale: 7.0, seed: 2))),
+//!     ],
+//! )
+//! ```
+
+use std::{error::Error, fmt, fs, path::Path};
+
+use nalgebra::{Matrix2, Vector2};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    combine::{Add, Blend, Max, Min, Multiply, Select, Subtract},
+    curve::{Curve, Terrace},
+    hashed_perlin::HashedPerlin,
+    hashed_worley::HashedWorley,
+    modifier::{Abs, Clamp, Exponent, Invert, Pow, ScaleBias},
+    noise::Noise,
+    open_simplex::OpenSimplex,
+    perlin::Perlin,
+    simplex::Simplex,
+    stack::{GradientFunction, Stack},
+    transform::{Affine2, Rotate, ScaleDomain, Translate},
+    turbulence::{finest_lattice, Turbulence},
+    worley::Worley,
+};
+
+/// Error produced while loading or building a noise graph.
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The file could not be read.
+    Io(std::io::Error),
+    /// The text is not a valid graph, for example because of an unknown node kind.
+    Parse(String),
+    /// A node has a parameter outside of its valid range.
+    Invalid { path: String, message: String },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(err) => write!(f, "failed to read noise config: {}", err),
+            ConfigError::Parse(message) => write!(f, "failed to parse noise config: {}", message),
+            ConfigError::Invalid { path, message } => {
+                write!(f, "invalid node at {}: {}", path, message)
+            }
+        }
+    }
+}
+
+impl Error for ConfigError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ConfigError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// A weighted layer of a stack node.
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LayerConfig {
+    pub weight: f32,
+    pub noise: NodeConfig,
+}
+
+/// A node of a noise graph.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+pub enum NodeConfig {
+    Perlin {
+        shape: (usize, usize),
+        seed: u64,
+    },
+    Simplex {
+        scale: f32,
+        seed: u64,
+    },
+    OpenSimplex {
+        scale: f32,
+        seed: u64,
+    },
+    Worley {
+        points: usize,
+        seed: u64,
+    },
+    /// Unbounded with `scale` cells per unit, or tiling the unit square with a lattice of `period` cells.
+    HashedPerlin {
+        #[serde(default)]
+        scale: Option<f32>,
+        #[serde(default)]
+        period: Option<(usize, usize)>,
+        seed: u64,
+    },
+    HashedWorley {
+        scale: f32,
+        seed: u64,
+    },
+    Stack {
+        #[serde(default = "noop")]
+        gradient_function: GradientFunction,
+        layers: Vec<LayerConfig>,
+    },
+    Add {
+        a: Box<NodeConfig>,
+        b: Box<NodeConfig>,
+    },
+    Subtract {
+        a: Box<NodeConfig>,
+        b: Box<NodeConfig>,
+    },
+    Multiply {
+        a: Box<NodeConfig>,
+        b: Box<NodeConfig>,
+    },
+    Min {
+        a: Box<NodeConfig>,
+        b: Box<NodeConfig>,
+    },
+    Max {
+        a: Box<NodeConfig>,
+        b: Box<NodeConfig>,
+    },
+    Blend {
+        a: Box<NodeConfig>,
+        b: Box<NodeConfig>,
+        control: Box<NodeConfig>,
+    },
+    Select {
+        a: Box<NodeConfig>,
+        b: Box<NodeConfig>,
+        control: Box<NodeConfig>,
+        threshold: f32,
+        #[serde(default)]
+        falloff: f32,
+    },
+    ScaleBias {
+        source: Box<NodeConfig>,
+        scale: f32,
+        bias: f32,
+    },
+    Clamp {
+        source: Box<NodeConfig>,
+        lower: f32,
+        upper: f32,
+        #[serde(default)]
+        smoothness: f32,
+    },
+    Abs {
+        source: Box<NodeConfig>,
+    },
+    Pow {
+        source: Box<NodeConfig>,
+        exponent: f32,
+    },
+    Invert {
+        source: Box<NodeConfig>,
+    },
+    Exponent {
+        source: Box<NodeConfig>,
+        exponent: f32,
+    },
+    Terrace {
+        source: Box<NodeConfig>,
+        points: Vec<f32>,
+        #[serde(default)]
+        invert: bool,
+    },
+    Curve {
+        source: Box<NodeConfig>,
+        points: Vec<(f32, f32)>,
+    },
+    Translate {
+        source: Box<NodeConfig>,
+        offset: (f32, f32),
+    },
+    Rotate {
+        source: Box<NodeConfig>,
+        angle: f32,
+    },
+    ScaleDomain {
+        source: Box<NodeConfig>,
+        scale: (f32, f32),
+    },
+    Affine {
+        source: Box<NodeConfig>,
+        matrix: [[f32; 2]; 2],
+        #[serde(default)]
+        translation: (f32, f32),
+    },
+    Turbulence {
+        source: Box<NodeConfig>,
+        power: f32,
+        roughness: usize,
+        frequency: usize,
+        seed: u64,
+    },
+}
+
+impl NodeConfig {
+    /// Parses a graph from RON.
+    ///
+    /// Optional parameters are given by value, without wrapping them in `Some`.
+    pub fn from_ron(text: &str) -> Result<Self, ConfigError> {
+        ron::Options::default()
+            .with_default_extension(ron::extensions::Extensions::IMPLICIT_SOME)
+            .from_str(text)
+            .map_err(|err| ConfigError::Parse(err.to_string()))
+    }
+
+    /// Parses a graph from JSON.
+    pub fn from_json(text: &str) -> Result<Self, ConfigError> {
+        serde_json::from_str(text).map_err(|err| ConfigError::Parse(err.to_string()))
+    }
+
+    /// Reads a graph from a `.ron` or `.json` file.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path).map_err(ConfigError::Io)?;
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("ron") => Self::from_ron(&text),
+            Some("json") => Self::from_json(&text),
+            _ => Err(ConfigError::Parse(format!(
+                "unrecognised file extension for {}, expected .ron or .json",
+                path.display()
+            ))),
+        }
+    }
+
+    /// Builds the noise function described by this graph, checking every parameter.
+    pub fn build(&self) -> Result<Box<dyn Noise>, ConfigError> {
+        self.build_at("root")
+    }
+
+    fn build_at(&self, path: &str) -> Result<Box<dyn Noise>, ConfigError> {
+        let child = |name: &str, node: &NodeConfig| node.build_at(&format!("{}.{}", path, name));
+        let invalid = |message: String| ConfigError::Invalid {
+            path: path.to_string(),
+            message,
+        };
+
+        let noise: Box<dyn Noise> = match self {
+            NodeConfig::Perlin { shape, seed } => {
+                if shape.0 == 0 || shape.1 == 0 {
+                    return Err(invalid(format!(
+                        "perlin shape must be non-zero, got {:?}",
+                        shape
+                    )));
+                }
+                Box::new(Perlin::from_seed(*shape, *seed))
+            }
+            NodeConfig::Simplex { scale, seed } => {
+                check_positive(*scale, "simplex scale").map_err(invalid)?;
+                Box::new(Simplex::from_seed(*scale, *seed))
+            }
+            NodeConfig::OpenSimplex { scale, seed } => {
+                check_positive(*scale, "open simplex scale").map_err(invalid)?;
+                Box::new(OpenSimplex::from_seed(*scale, *seed))
+            }
+            NodeConfig::Worley { points, seed } => {
+                if *points == 0 {
+                    return Err(invalid("worley must have at least one point".to_string()));
+                }
+                Box::new(Worley::from_seed(*points, *seed))
+            }
+            NodeConfig::HashedPerlin {
+                scale,
+                period,
+                seed,
+            } => match (scale, period) {
+                (Some(scale), None) => {
+                    check_positive(*scale, "hashed perlin scale").map_err(invalid)?;
+                    Box::new(HashedPerlin::from_seed(*scale, *seed))
+                }
+                (None, Some(period)) => {
+                    let fits = |cells: usize| cells > 0 && u32::try_from(cells).is_ok();
+                    if !fits(period.0) || !fits(period.1) {
+                        return Err(invalid(format!(
+                            "hashed perlin period must be non-zero and fit in u32, got {:?}",
+                            period
+                        )));
+                    }
+                    Box::new(HashedPerlin::tileable_from_seed(*period, *seed))
+                }
+                _ => {
+                    return Err(invalid(
+                        "hashed perlin needs exactly one of scale and period".to_string(),
+                    ))
+                }
+            },
+            NodeConfig::HashedWorley { scale, seed } => {
+                check_positive(*scale, "hashed worley scale").map_err(invalid)?;
+                Box::new(HashedWorley::from_seed(*scale, *seed))
+            }
+            NodeConfig::Stack {
+                gradient_function,
+                layers,
+            } => {
+                if layers.is_empty() {
+                    return Err(invalid("stack must have at least one layer".to_string()));
+                }
+                let mut noise_weights = Vec::with_capacity(layers.len());
+                for (index, layer) in layers.iter().enumerate() {
+                    if !layer.weight.is_finite() || layer.weight < 0.0 {
+                        return Err(invalid(format!(
+                            "layer {} weight must be non-negative, got {}",
+                            index, layer.weight
+                        )));
+                    }
+                    let noise = child(&format!("layers[{}].noise", index), &layer.noise)?;
+                    noise_weights.push((noise, layer.weight));
+                }
+                Box::new(Stack::new(gradient_function.clone(), noise_weights))
+            }
+            NodeConfig::Add { a, b } => Box::new(Add::new(child("a", a)?, child("b", b)?)),
+            NodeConfig::Subtract { a, b } => {
+                Box::new(Subtract::new(child("a", a)?, child("b", b)?))
+            }
+            NodeConfig::Multiply { a, b } => {
+                Box::new(Multiply::new(child("a", a)?, child("b", b)?))
+            }
+            NodeConfig::Min { a, b } => Box::new(Min::new(child("a", a)?, child("b", b)?)),
+            NodeConfig::Max { a, b } => Box::new(Max::new(child("a", a)?, child("b", b)?)),
+            NodeConfig::Blend { a, b, control } => Box::new(Blend::new(
+                child("a", a)?,
+                child("b", b)?,
+                child("control", control)?,
+            )),
+            NodeConfig::Select {
+                a,
+                b,
+                control,
+                threshold,
+                falloff,
+            } => {
+                if !falloff.is_finite() || *falloff < 0.0 {
+                    return Err(invalid(format!(
+                        "select falloff must be non-negative, got {}",
+                        falloff
+                    )));
+                }
+                Box::new(Select::new(
+                    child("a", a)?,
+                    child("b", b)?,
+                    child("control", control)?,
+                    *threshold,
+                    *falloff,
+                ))
+            }
+            NodeConfig::ScaleBias {
+                source,
+                scale,
+                bias,
+            } => Box::new(ScaleBias::new(child("source", source)?, *scale, *bias)),
+            NodeConfig::Clamp {
+                source,
+                lower,
+                upper,
+                smoothness,
+            } => {
+                if !lower.is_finite() || !upper.is_finite() {
+                    return Err(invalid(format!(
+                        "clamp bounds must be finite, got {} and {}",
+                        lower, upper
+                    )));
+                }
+                if lower > upper {
+                    return Err(invalid(format!(
+                        "clamp lower bound {} is above upper bound {}",
+                        lower, upper
+                    )));
+                }
+                if !smoothness.is_finite() || *smoothness < 0.0 {
+                    return Err(invalid(format!(
+                        "clamp smoothness must be non-negative, got {}",
+                        smoothness
+                    )));
+                }
+                Box::new(Clamp::smooth(
+                    child("source", source)?,
+                    *lower,
+                    *upper,
+                    *smoothness,
+                ))
+            }
+            NodeConfig::Abs { source } => Box::new(Abs::new(child("source", source)?)),
+            NodeConfig::Pow { source, exponent } => {
+                Box::new(Pow::new(child("source", source)?, *exponent))
+            }
+            NodeConfig::Invert { source } => Box::new(Invert::new(child("source", source)?)),
+            NodeConfig::Exponent { source, exponent } => {
+                Box::new(Exponent::new(child("source", source)?, *exponent))
+            }
+            NodeConfig::Terrace {
+                source,
+                points,
+                invert,
+            } => {
+                if let Some(point) = points.iter().find(|p| !p.is_finite()) {
+                    return Err(invalid(format!(
+                        "terrace control points must be finite, got {}",
+                        point
+                    )));
+                }
+                let mut distinct = points.clone();
+                distinct.sort_by(f32::total_cmp);
+                distinct.dedup();
+                if distinct.len() < 2 {
+                    return Err(invalid(
+                        "terrace needs at least two distinct control points".to_string(),
+                    ));
+                }
+                Box::new(Terrace::new(child("source", source)?, distinct, *invert))
+            }
+            NodeConfig::Curve { source, points } => {
+                if let Some(point) = points.iter().find(|p| !p.0.is_finite() || !p.1.is_finite()) {
+                    return Err(invalid(format!(
+                        "curve control points must be finite, got {:?}",
+                        point
+                    )));
+                }
+                let mut inputs: Vec<f32> = points.iter().map(|p| p.0).collect();
+                inputs.sort_by(f32::total_cmp);
+                inputs.dedup();
+                if inputs.len() < 2 {
+                    return Err(invalid(
+                        "curve needs at least two control points with distinct inputs".to_string(),
+                    ));
+                }
+                Box::new(Curve::new(child("source", source)?, points.clone()))
+            }
+            NodeConfig::Translate { source, offset } => Box::new(Translate::new(
+                child("source", source)?,
+                Vector2::new(offset.0, offset.1),
+            )),
+            NodeConfig::Rotate { source, angle } => {
+                Box::new(Rotate::new(child("source", source)?, *angle))
+            }
+            NodeConfig::ScaleDomain { source, scale } => Box::new(ScaleDomain::new(
+                child("source", source)?,
+                Vector2::new(scale.0, scale.1),
+            )),
+            NodeConfig::Affine {
+                source,
+                matrix,
+                translation,
+            } => Box::new(Affine2::new(
+                child("source", source)?,
+                Matrix2::new(matrix[0][0], matrix[0][1], matrix[1][0], matrix[1][1]),
+                Vector2::new(translation.0, translation.1),
+            )),
+            NodeConfig::Turbulence {
+                source,
+                power,
+                roughness,
+                frequency,
+                seed,
+            } => {
+                if *roughness == 0 || *frequency == 0 {
+                    return Err(invalid(format!(
+                        "turbulence roughness and frequency must be non-zero, got {} and {}",
+                        roughness, frequency
+                    )));
+                }
+                match finest_lattice(*roughness, *frequency) {
+                    Some(cells) if cells <= Turbulence::MAX_LATTICE => {}
+                    _ => {
+                        return Err(invalid(format!(
+                            "turbulence finest lattice must be at most {} cells, \
+                             got frequency {} and roughness {}",
+                            Turbulence::MAX_LATTICE,
+                            frequency,
+                            roughness
+                        )))
+                    }
+                }
+                Box::new(Turbulence::from_seed(
+                    child("source", source)?,
+                    *power,
+                    *roughness,
+                    *frequency,
+                    *seed,
+                ))
+            }
+        };
+
+        Ok(noise)
+    }
+}
+
+fn noop() -> GradientFunction {
+    GradientFunction::Noop
+}
+
+fn check_positive(value: f32, name: &str) -> Result<(), String> {
+    if value.is_finite() && value > 0.0 {
+        Ok(())
+    } else {
+        Err(format!("{} must be positive, got {}", name, value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_fields_are_rejected() {
+        let ron = r#"(kind: "select", a: (kind: "simplex", scale: 2.0, seed: 1),
+            b: (kind: "simplex", scale: 3.0, seed: 2), control: (kind: "simplex", scale: 1.0, seed: 3),
+            threshold: 0.0, fallof: 0.1)"#;
+        assert!(matches!(
+            NodeConfig::from_ron(ron),
+            Err(ConfigError::Parse(_))
+        ));
+
+        let json = r#"{"kind": "stack", "layers": [{"weight": 1.0, "wieght": 2.0,
+            "noise": {"kind": "perlin", "shape": [2, 2], "seed": 1}}]}"#;
+        assert!(matches!(
+            NodeConfig::from_json(json),
+            Err(ConfigError::Parse(_))
+        ));
+
+        let json = r#"{"kind": "perlin", "shape": [2, 2], "seed": 1}"#;
+        assert!(NodeConfig::from_json(json).unwrap().build().is_ok());
+    }
+
+    #[test]
+    fn hashed_perlin_takes_a_scale_or_a_period() {
+        let unbounded = NodeConfig::from_ron(r#"(kind: "hashed_perlin", scale: 4.0, seed: 1)"#);
+        let tileable = NodeConfig::from_ron(r#"(kind: "hashed_perlin", period: (4, 4), seed: 1)"#);
+        let (unbounded, tileable) = (
+            unbounded.unwrap().build().unwrap(),
+            tileable.unwrap().build().unwrap(),
+        );
+        assert_eq!(
+            tileable.sample(0.3, 0.6),
+            HashedPerlin::tileable_from_seed((4, 4), 1).sample(0.3, 0.6)
+        );
+        assert_eq!(tileable.sample(0.3, 0.6), unbounded.sample(0.3, 0.6));
+        assert_ne!(tileable.sample(0.9, 0.6), unbounded.sample(0.9, 0.6));
+
+        let json = r#"{"kind": "hashed_perlin", "period": [3, 5], "seed": 1}"#;
+        assert!(NodeConfig::from_json(json).unwrap().build().is_ok());
+
+        for ron in [
+            r#"(kind: "hashed_perlin", seed: 1)"#,
+            r#"(kind: "hashed_perlin", scale: 4.0, period: (4, 4), seed: 1)"#,
+            r#"(kind: "hashed_perlin", period: (0, 4), seed: 1)"#,
+        ] {
+            let node = NodeConfig::from_ron(ron).unwrap();
+            assert!(matches!(node.build(), Err(ConfigError::Invalid { .. })));
+        }
+    }
+
+    #[test]
+    fn invalid_parameters_are_reported() {
+        let source = || {
+            Box::new(NodeConfig::Simplex {
+                scale: 2.0,
+                seed: 1,
+            })
+        };
+        let invalid = [
+            NodeConfig::Curve {
+                source: source(),
+                points: vec![(-1.0, -1.0), (0.0, f32::NAN), (1.0, 1.0)],
+            },
+            NodeConfig::Terrace {
+                source: source(),
+                points: vec![-1.0, f32::INFINITY, 1.0],
+                invert: false,
+            },
+            NodeConfig::Clamp {
+                source: source(),
+                lower: f32::NAN,
+                upper: 1.0,
+                smoothness: 0.0,
+            },
+            NodeConfig::Turbulence {
+                source: source(),
+                power: 0.1,
+                roughness: 70,
+                frequency: 1,
+                seed: 1,
+            },
+        ];
+        for node in &invalid {
+            assert!(matches!(node.build(), Err(ConfigError::Invalid { .. })));
+        }
+    }
+}
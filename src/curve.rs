@@ -0,0 +1,254 @@
+use nalgebra::Vector2;
+
+use crate::{
+    float::Float,
+    noise::{Noise, Noise64, NoiseAt},
+};
+
+/// Remaps a noise function through a monotone cubic spline.
+///
+/// The spline passes through each `(input, output)` control point and never overshoots between them.
+/// Values outside the range of the control points are clamped to the first or last output.
+pub struct Curve<N = Box<dyn Noise>> {
+    noise: N,
+    spline: MonotoneSpline,
+}
+
+impl<N> Curve<N> {
+    pub fn new(noise: N, control_points: Vec<(f32, f32)>) -> Self {
+        Self {
+            noise,
+            spline: MonotoneSpline::new(control_points),
+        }
+    }
+
+    fn sample_generic<F: Float>(&self, x: F, y: F) -> F
+    where
+        N: NoiseAt<F>,
+    {
+        self.spline.evaluate(self.noise.sample_at(x, y)).0
+    }
+
+    fn gradient_generic<F: Float>(&self, x: F, y: F) -> Vector2<F>
+    where
+        N: NoiseAt<F>,
+    {
+        let (_, derivative) = self.spline.evaluate(self.noise.sample_at(x, y));
+        self.noise.gradient_at(x, y) * derivative
+    }
+}
+
+impl<N: Noise> Noise for Curve<N> {
+    fn sample(&self, x: f32, y: f32) -> f32 {
+        self.sample_generic(x, y)
+    }
+
+    fn gradient(&self, x: f32, y: f32) -> Vector2<f32> {
+        self.gradient_generic(x, y)
+    }
+}
+
+impl<N: Noise64> Noise64 for Curve<N> {
+    fn sample_f64(&self, x: f64, y: f64) -> f64 {
+        self.sample_generic(x, y)
+    }
+
+    fn gradient_f64(&self, x: f64, y: f64) -> Vector2<f64> {
+        self.gradient_generic(x, y)
+    }
+}
+
+/// Maps a noise function onto a series of terraces.
+///
+/// Between two adjacent control points the value follows a quadratic curve that is flat at the lower point,
+/// producing a plateau followed by a steep rise. Inverting flips the curve so the plateau sits at the upper point.
+pub struct Terrace<N = Box<dyn Noise>> {
+    noise: N,
+    control_points: Vec<f32>,
+    invert: bool,
+}
+
+impl<N> Terrace<N> {
+    pub fn new(noise: N, mut control_points: Vec<f32>, invert: bool) -> Self {
+        control_points.sort_by(f32::total_cmp);
+        control_points.dedup();
+        assert!(control_points.len() >= 2);
+
+        Self {
+            noise,
+            control_points,
+            invert,
+        }
+    }
+
+    // Terraced value and its derivative with respect to the input value.
+    fn terrace<F: Float>(&self, value: F) -> (F, F) {
+        let point = |index: usize| F::cast(self.control_points[index] as f64);
+        let last = self.control_points.len() - 1;
+        if value <= point(0) {
+            return (point(0), F::zero());
+        }
+        if value >= point(last) {
+            return (point(last), F::zero());
+        }
+
+        let index = self
+            .control_points
+            .partition_point(|&p| F::cast(p as f64) <= value)
+            - 1;
+        let (lower, upper) = (point(index), point(index + 1));
+        let alpha = (value - lower) / (upper - lower);
+        if self.invert {
+            let alpha = F::one() - alpha;
+            (
+                upper + alpha * alpha * (lower - upper),
+                F::cast(2.0) * alpha,
+            )
+        } else {
+            (
+                lower + alpha * alpha * (upper - lower),
+                F::cast(2.0) * alpha,
+            )
+        }
+    }
+
+    fn sample_generic<F: Float>(&self, x: F, y: F) -> F
+    where
+        N: NoiseAt<F>,
+    {
+        self.terrace(self.noise.sample_at(x, y)).0
+    }
+
+    fn gradient_generic<F: Float>(&self, x: F, y: F) -> Vector2<F>
+    where
+        N: NoiseAt<F>,
+    {
+        let (_, derivative) = self.terrace(self.noise.sample_at(x, y));
+        self.noise.gradient_at(x, y) * derivative
+    }
+}
+
+impl<N: Noise> Noise for Terrace<N> {
+    fn sample(&self, x: f32, y: f32) -> f32 {
+        self.sample_generic(x, y)
+    }
+
+    fn gradient(&self, x: f32, y: f32) -> Vector2<f32> {
+        self.gradient_generic(x, y)
+    }
+}
+
+impl<N: Noise64> Noise64 for Terrace<N> {
+    fn sample_f64(&self, x: f64, y: f64) -> f64 {
+        self.sample_generic(x, y)
+    }
+
+    fn gradient_f64(&self, x: f64, y: f64) -> Vector2<f64> {
+        self.gradient_generic(x, y)
+    }
+}
+
+// Piecewise cubic Hermite spline with Fritsch-Carlson tangents, taken as Brodlie's weighted harmonic mean
+// of the neighbouring slopes, which preserve monotonicity of the data.
+struct MonotoneSpline {
+    xs: Vec<f32>,
+    ys: Vec<f32>,
+    tangents: Vec<f32>,
+}
+
+impl MonotoneSpline {
+    fn new(mut points: Vec<(f32, f32)>) -> Self {
+        points.sort_by(|a, b| a.0.total_cmp(&b.0));
+        points.dedup_by(|a, b| a.0 == b.0);
+        assert!(points.len() >= 2);
+
+        let (xs, ys): (Vec<f32>, Vec<f32>) = points.into_iter().unzip();
+        let n = xs.len();
+
+        let widths: Vec<f32> = xs.windows(2).map(|w| w[1] - w[0]).collect();
+        let slopes: Vec<f32> = (0..n - 1)
+            .map(|k| (ys[k + 1] - ys[k]) / widths[k])
+            .collect();
+
+        let mut tangents = vec![0.0; n];
+        tangents[0] = slopes[0];
+        tangents[n - 1] = slopes[n - 2];
+        for k in 1..n - 1 {
+            if slopes[k - 1] * slopes[k] > 0.0 {
+                let w1 = 2.0 * widths[k] + widths[k - 1];
+                let w2 = widths[k] + 2.0 * widths[k - 1];
+                tangents[k] = (w1 + w2) / (w1 / slopes[k - 1] + w2 / slopes[k]);
+            }
+        }
+
+        Self { xs, ys, tangents }
+    }
+
+    // Spline value and derivative at `x`, clamped to the end points outside the control range.
+    fn evaluate<F: Float>(&self, x: F) -> (F, F) {
+        let c = F::cast;
+        let (xs, ys) = (
+            |k: usize| c(self.xs[k] as f64),
+            |k: usize| c(self.ys[k] as f64),
+        );
+        let last = self.xs.len() - 1;
+        if x <= xs(0) {
+            return (ys(0), F::zero());
+        }
+        if x >= xs(last) {
+            return (ys(last), F::zero());
+        }
+
+        let k = self.xs.partition_point(|&p| c(p as f64) <= x) - 1;
+        let h = xs(k + 1) - xs(k);
+        let t = (x - xs(k)) / h;
+        let t2 = t * t;
+        let t3 = t2 * t;
+
+        let (y0, y1) = (ys(k), ys(k + 1));
+        let tangent = |k: usize| c(self.tangents[k] as f64);
+        let (m0, m1) = (tangent(k) * h, tangent(k + 1) * h);
+
+        let value = (c(2.0) * t3 - c(3.0) * t2 + F::one()) * y0
+            + (t3 - c(2.0) * t2 + t) * m0
+            + (c(-2.0) * t3 + c(3.0) * t2) * y1
+            + (t3 - t2) * m1;
+        let derivative = ((c(6.0) * t2 - c(6.0) * t) * y0
+            + (c(3.0) * t2 - c(4.0) * t + F::one()) * m0
+            + (c(-6.0) * t2 + c(6.0) * t) * y1
+            + (c(3.0) * t2 - c(2.0) * t) * m1)
+            / h;
+
+        (value, derivative)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        noise::{assert_f64_matches, assert_gradient_matches},
+        Perlin,
+    };
+
+    fn perlin() -> Box<dyn Noise> {
+        Box::new(Perlin::from_seed((4, 4), 1))
+    }
+
+    #[test]
+    fn gradients_match_finite_differences() {
+        let points = vec![(-1.0, -1.0), (-0.2, 0.1), (0.3, 0.2), (1.0, 1.0)];
+        assert_gradient_matches(&Curve::new(perlin(), points));
+        assert_gradient_matches(&Terrace::new(perlin(), vec![-1.0, -0.3, 0.2, 1.0], false));
+        assert_gradient_matches(&Terrace::new(perlin(), vec![-1.0, -0.3, 0.2, 1.0], true));
+    }
+
+    #[test]
+    fn f64_matches_f32() {
+        let perlin = || Perlin::from_seed((4, 4), 1);
+        let points = vec![(-1.0, -1.0), (-0.2, 0.1), (0.3, 0.2), (1.0, 1.0)];
+        assert_f64_matches(&Curve::new(perlin(), points));
+        assert_f64_matches(&Terrace::new(perlin(), vec![-1.0, -0.3, 0.2, 1.0], false));
+        assert_f64_matches(&Terrace::new(perlin(), vec![-1.0, -0.3, 0.2, 1.0], true));
+    }
+}
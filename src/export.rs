@@ -0,0 +1,279 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use ndarray::Array2;
+
+/// File formats for exporting sampled heightmaps.
+///
+/// Row 0 of the array is the top of the image.
+/// The integer formats expect values normalised to [0, 1] and clamp anything outside that range,
+/// while the floating point formats store values unchanged.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HeightmapFormat {
+    /// 16-bit grayscale PNG.
+    Png16,
+    /// Headerless little-endian `u16` values, as imported by Unity and Unreal.
+    R16,
+    /// Headerless little-endian `f32` values.
+    R32,
+    /// Portable float map, single channel.
+    Pfm,
+    /// Uncompressed single channel (`Y`) 32-bit float OpenEXR.
+    Exr,
+}
+
+impl HeightmapFormat {
+    /// Guesses the format from a file extension: `png`, `r16`, `r32`/`raw`, `pfm` or `exr`.
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "png" => Some(HeightmapFormat::Png16),
+            "r16" => Some(HeightmapFormat::R16),
+            "r32" | "raw" => Some(HeightmapFormat::R32),
+            "pfm" => Some(HeightmapFormat::Pfm),
+            "exr" => Some(HeightmapFormat::Exr),
+            _ => None,
+        }
+    }
+}
+
+/// Saves a heightmap, choosing the format from the file extension.
+pub fn save_heightmap<P: AsRef<Path>>(data: &Array2<f32>, path: P) -> io::Result<()> {
+    let path = path.as_ref();
+    let format = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .and_then(HeightmapFormat::from_extension)
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("unrecognised heightmap extension for {}", path.display()),
+            )
+        })?;
+
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_heightmap(data, format, &mut writer)?;
+    writer.flush()
+}
+
+/// Writes a heightmap in the given format.
+pub fn write_heightmap<W: Write>(
+    data: &Array2<f32>,
+    format: HeightmapFormat,
+    writer: W,
+) -> io::Result<()> {
+    match format {
+        HeightmapFormat::Png16 => write_png16(data, writer),
+        HeightmapFormat::R16 => write_r16(data, writer),
+        HeightmapFormat::R32 => write_r32(data, writer),
+        HeightmapFormat::Pfm => write_pfm(data, writer),
+        HeightmapFormat::Exr => write_exr(data, writer),
+    }
+}
+
+fn to_u16(value: f32) -> u16 {
+    (value.clamp(0.0, 1.0) * u16::MAX as f32).round() as u16
+}
+
+fn write_png16<W: Write>(data: &Array2<f32>, writer: W) -> io::Result<()> {
+    let (height, width) = data.dim();
+    // PNG stores 16-bit samples big-endian
+    let bytes: Vec<u8> = data.iter().flat_map(|&v| to_u16(v).to_be_bytes()).collect();
+
+    let mut encoder = png::Encoder::new(writer, width as u32, height as u32);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Sixteen);
+    encoder.write_header()?.write_image_data(&bytes)?;
+    Ok(())
+}
+
+fn write_r16<W: Write>(data: &Array2<f32>, mut writer: W) -> io::Result<()> {
+    for &v in data.iter() {
+        writer.write_all(&to_u16(v).to_le_bytes())?;
+    }
+    Ok(())
+}
+
+fn write_r32<W: Write>(data: &Array2<f32>, mut writer: W) -> io::Result<()> {
+    for &v in data.iter() {
+        writer.write_all(&v.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+fn write_pfm<W: Write>(data: &Array2<f32>, mut writer: W) -> io::Result<()> {
+    let (height, width) = data.dim();
+    // A negative scale marks the data as little-endian
+    write!(writer, "Pf\n{} {}\n-1.0\n", width, height)?;
+
+    // Rows are stored from the bottom of the image to the top
+    for row in data.rows().into_iter().rev() {
+        for &v in row.iter() {
+            writer.write_all(&v.to_le_bytes())?;
+        }
+    }
+    Ok(())
+}
+
+fn write_exr<W: Write>(data: &Array2<f32>, mut writer: W) -> io::Result<()> {
+    let (height, width) = data.dim();
+    let max_x = width as i32 - 1;
+    let max_y = height as i32 - 1;
+
+    let mut header = Vec::new();
+    header.extend_from_slice(&0x0131_2f76_u32.to_le_bytes());
+    // Version 2, single-part scanline image
+    header.extend_from_slice(&2_u32.to_le_bytes());
+
+    // One FLOAT channel named Y, sampled at every pixel
+    let mut channels = Vec::new();
+    channels.extend_from_slice(b"Y\0");
+    channels.extend_from_slice(&2_i32.to_le_bytes());
+    channels.extend_from_slice(&[0, 0, 0, 0]);
+    channels.extend_from_slice(&1_i32.to_le_bytes());
+    channels.extend_from_slice(&1_i32.to_le_bytes());
+    channels.push(0);
+    exr_attribute(&mut header, "channels", "chlist", &channels);
+
+    exr_attribute(&mut header, "compression", "compression", &[0]);
+    let window: Vec<u8> = [0, 0, max_x, max_y]
+        .iter()
+        .flat_map(|v| v.to_le_bytes())
+        .collect();
+    exr_attribute(&mut header, "dataWindow", "box2i", &window);
+    exr_attribute(&mut header, "displayWindow", "box2i", &window);
+    exr_attribute(&mut header, "lineOrder", "lineOrder", &[0]);
+    exr_attribute(
+        &mut header,
+        "pixelAspectRatio",
+        "float",
+        &1.0_f32.to_le_bytes(),
+    );
+    exr_attribute(&mut header, "screenWindowCenter", "v2f", &[0; 8]);
+    exr_attribute(
+        &mut header,
+        "screenWindowWidth",
+        "float",
+        &1.0_f32.to_le_bytes(),
+    );
+    header.push(0);
+
+    // Without compression each block holds a single scanline: y, byte count, then the pixels
+    let line_bytes = 4 * width;
+    let block_bytes = 8 + line_bytes;
+    let first_block = header.len() + 8 * height;
+    writer.write_all(&header)?;
+    for y in 0..height {
+        writer.write_all(&((first_block + y * block_bytes) as u64).to_le_bytes())?;
+    }
+    for (y, row) in data.rows().into_iter().enumerate() {
+        writer.write_all(&(y as i32).to_le_bytes())?;
+        writer.write_all(&(line_bytes as i32).to_le_bytes())?;
+        for &v in row.iter() {
+            writer.write_all(&v.to_le_bytes())?;
+        }
+    }
+    Ok(())
+}
+
+fn exr_attribute(header: &mut Vec<u8>, name: &str, kind: &str, value: &[u8]) {
+    header.extend_from_slice(name.as_bytes());
+    header.push(0);
+    header.extend_from_slice(kind.as_bytes());
+    header.push(0);
+    header.extend_from_slice(&(value.len() as i32).to_le_bytes());
+    header.extend_from_slice(value);
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::array;
+
+    use super::*;
+
+    // Two rows of three, with values outside [0, 1] to exercise the clamping of the integer formats.
+    fn heights() -> Array2<f32> {
+        array![[0.0, 0.25, -0.5], [1.5, 0.75, 1.0]]
+    }
+
+    fn write(format: HeightmapFormat) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        write_heightmap(&heights(), format, &mut bytes).unwrap();
+        bytes
+    }
+
+    fn f32_at(bytes: &[u8], offset: usize) -> f32 {
+        f32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn r16_is_clamped_little_endian() {
+        let bytes = write(HeightmapFormat::R16);
+        assert_eq!(bytes.len(), 2 * 6);
+        assert_eq!(bytes[..6], [0, 0, 0x00, 0x40, 0, 0]);
+        assert_eq!(bytes[6..8], [0xff, 0xff]);
+        assert_eq!(bytes[10..], [0xff, 0xff]);
+    }
+
+    #[test]
+    fn png16_is_clamped_big_endian() {
+        let bytes = write(HeightmapFormat::Png16);
+        let mut reader = png::Decoder::new(bytes.as_slice()).read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut pixels).unwrap();
+        assert_eq!((info.width, info.height), (3, 2));
+        assert_eq!(info.bit_depth, png::BitDepth::Sixteen);
+        assert_eq!(pixels[..6], [0, 0, 0x40, 0x00, 0, 0]);
+        assert_eq!(pixels[6..8], [0xff, 0xff]);
+    }
+
+    #[test]
+    fn r32_keeps_values() {
+        let bytes = write(HeightmapFormat::R32);
+        assert_eq!(bytes.len(), 4 * 6);
+        assert_eq!(f32_at(&bytes, 0), 0.0);
+        assert_eq!(f32_at(&bytes, 8), -0.5);
+        assert_eq!(f32_at(&bytes, 20), 1.0);
+    }
+
+    #[test]
+    fn pfm_is_little_endian_bottom_up() {
+        let bytes = write(HeightmapFormat::Pfm);
+        let header = b"Pf\n3 2\n-1.0\n";
+        assert_eq!(bytes[..header.len()], header[..]);
+        assert_eq!(bytes.len(), header.len() + 4 * 6);
+        // The bottom row comes first
+        assert_eq!(f32_at(&bytes, header.len()), 1.5);
+        assert_eq!(f32_at(&bytes, bytes.len() - 4), -0.5);
+    }
+
+    #[test]
+    fn exr_offsets_point_at_scanlines() {
+        let bytes = write(HeightmapFormat::Exr);
+        assert_eq!(bytes[..8], [0x76, 0x2f, 0x31, 0x01, 2, 0, 0, 0]);
+        assert_eq!(bytes[8..25], *b"channels\0chlist\0\x13");
+
+        // The header ends with a null byte, followed by one offset per scanline
+        let block = 8 + 4 * 3;
+        let offset_at = |i: usize| u64::from_le_bytes(bytes[i..i + 8].try_into().unwrap()) as usize;
+        let first_block = bytes.len() - 2 * block;
+        let table = first_block - 2 * 8;
+        assert_eq!(bytes[table - 1], 0);
+        assert_eq!(offset_at(table), first_block);
+        assert_eq!(offset_at(table + 8), first_block + block);
+
+        // Each scanline holds its y coordinate, its byte count and then the pixels
+        assert_eq!(
+            bytes[first_block..first_block + 8],
+            [0, 0, 0, 0, 12, 0, 0, 0]
+        );
+        assert_eq!(f32_at(&bytes, first_block + 8), 0.0);
+        assert_eq!(
+            bytes[first_block + block..first_block + block + 4],
+            [1, 0, 0, 0]
+        );
+        assert_eq!(f32_at(&bytes, bytes.len() - 4), 1.0);
+    }
+}
@@ -0,0 +1,29 @@
+use nalgebra::RealField;
+
+// Precision that generators are evaluated in internally, so that `Noise` and `Noise64` share one implementation.
+// Seeded state stays in `f32`, so a seed produces the same field at either precision.
+pub(crate) trait Float: RealField + Copy {
+    /// Converts a constant, rounding to the nearest representable value.
+    fn cast(value: f64) -> Self;
+    fn to_f64(self) -> f64;
+}
+
+impl Float for f32 {
+    fn cast(value: f64) -> Self {
+        value as f32
+    }
+
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+}
+
+impl Float for f64 {
+    fn cast(value: f64) -> Self {
+        value
+    }
+
+    fn to_f64(self) -> f64 {
+        self
+    }
+}
@@ -0,0 +1,120 @@
+use nalgebra::{Matrix2, Vector2};
+
+use crate::{
+    hashed_perlin::HashedPerlin,
+    hashed_worley::HashedWorley,
+    noise::{Noise, Noise64},
+    open_simplex::OpenSimplex,
+    perlin::Perlin,
+    simplex::Simplex,
+    worley::Worley,
+};
+
+/// Any of the built-in noise generators.
+///
+/// Unlike `Box<dyn Noise>` the concrete generator is known, so a `Stack<Generator>` can be serialized.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type"))]
+pub enum Generator {
+    Perlin(Perlin),
+    Simplex(Simplex),
+    OpenSimplex(OpenSimplex),
+    Worley(Worley),
+    HashedPerlin(HashedPerlin),
+    HashedWorley(HashedWorley),
+}
+
+impl Noise for Generator {
+    fn sample(&self, x: f32, y: f32) -> f32 {
+        match self {
+            Generator::Perlin(noise) => noise.sample(x, y),
+            Generator::Simplex(noise) => noise.sample(x, y),
+            Generator::OpenSimplex(noise) => noise.sample(x, y),
+            Generator::Worley(noise) => noise.sample(x, y),
+            Generator::HashedPerlin(noise) => noise.sample(x, y),
+            Generator::HashedWorley(noise) => noise.sample(x, y),
+        }
+    }
+
+    fn gradient(&self, x: f32, y: f32) -> Vector2<f32> {
+        match self {
+            Generator::Perlin(noise) => noise.gradient(x, y),
+            Generator::Simplex(noise) => noise.gradient(x, y),
+            Generator::OpenSimplex(noise) => noise.gradient(x, y),
+            Generator::Worley(noise) => noise.gradient(x, y),
+            Generator::HashedPerlin(noise) => noise.gradient(x, y),
+            Generator::HashedWorley(noise) => noise.gradient(x, y),
+        }
+    }
+
+    fn hessian(&self, x: f32, y: f32) -> Matrix2<f32> {
+        match self {
+            Generator::Perlin(noise) => noise.hessian(x, y),
+            Generator::Simplex(noise) => noise.hessian(x, y),
+            Generator::OpenSimplex(noise) => noise.hessian(x, y),
+            Generator::Worley(noise) => noise.hessian(x, y),
+            Generator::HashedPerlin(noise) => noise.hessian(x, y),
+            Generator::HashedWorley(noise) => noise.hessian(x, y),
+        }
+    }
+}
+
+impl Noise64 for Generator {
+    fn sample_f64(&self, x: f64, y: f64) -> f64 {
+        match self {
+            Generator::Perlin(noise) => noise.sample_f64(x, y),
+            Generator::Simplex(noise) => noise.sample_f64(x, y),
+            Generator::OpenSimplex(noise) => noise.sample_f64(x, y),
+            Generator::Worley(noise) => noise.sample_f64(x, y),
+            Generator::HashedPerlin(noise) => noise.sample_f64(x, y),
+            Generator::HashedWorley(noise) => noise.sample_f64(x, y),
+        }
+    }
+
+    fn gradient_f64(&self, x: f64, y: f64) -> Vector2<f64> {
+        match self {
+            Generator::Perlin(noise) => noise.gradient_f64(x, y),
+            Generator::Simplex(noise) => noise.gradient_f64(x, y),
+            Generator::OpenSimplex(noise) => noise.gradient_f64(x, y),
+            Generator::Worley(noise) => noise.gradient_f64(x, y),
+            Generator::HashedPerlin(noise) => noise.gradient_f64(x, y),
+            Generator::HashedWorley(noise) => noise.gradient_f64(x, y),
+        }
+    }
+}
+
+impl From<Perlin> for Generator {
+    fn from(noise: Perlin) -> Self {
+        Generator::Perlin(noise)
+    }
+}
+
+impl From<Simplex> for Generator {
+    fn from(noise: Simplex) -> Self {
+        Generator::Simplex(noise)
+    }
+}
+
+impl From<OpenSimplex> for Generator {
+    fn from(noise: OpenSimplex) -> Self {
+        Generator::OpenSimplex(noise)
+    }
+}
+
+impl From<Worley> for Generator {
+    fn from(noise: Worley) -> Self {
+        Generator::Worley(noise)
+    }
+}
+
+impl From<HashedPerlin> for Generator {
+    fn from(noise: HashedPerlin) -> Self {
+        Generator::HashedPerlin(noise)
+    }
+}
+
+impl From<HashedWorley> for Generator {
+    fn from(noise: HashedWorley) -> Self {
+        Generator::HashedWorley(noise)
+    }
+}
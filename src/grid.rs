@@ -0,0 +1,64 @@
+use nalgebra::{Vector2, Vector3};
+use ndarray::{Array1, Array2, Array3};
+
+use crate::noise::{Noise, Noise1, Noise3};
+
+/// Samples a noise function over the unit square on a grid of `(height, width)` points.
+///
+/// Element `(i, j)` holds the value at `u = j / width`, `v = i / height`,
+/// so the grid does not repeat its first row or column and tiles seamlessly for tileable noise.
+pub fn sample_grid<N: Noise + ?Sized>(noise: &N, resolution: (usize, usize)) -> Array2<f32> {
+    let (height, width) = resolution;
+    Array2::from_shape_fn(resolution, |(i, j)| {
+        noise.sample(j as f32 / width as f32, i as f32 / height as f32)
+    })
+}
+
+/// Gradients of a noise function on the same grid as [`sample_grid`].
+pub fn gradient_grid<N: Noise + ?Sized>(
+    noise: &N,
+    resolution: (usize, usize),
+) -> Array2<Vector2<f32>> {
+    let (height, width) = resolution;
+    Array2::from_shape_fn(resolution, |(i, j)| {
+        noise.gradient(j as f32 / width as f32, i as f32 / height as f32)
+    })
+}
+
+/// Samples a 3D noise function over the unit cube on a grid of `(depth, height, width)` points.
+///
+/// Element `(k, i, j)` holds the value at `x = j / width`, `y = i / height`, `z = k / depth`,
+/// so each slice along the first axis is laid out like [`sample_grid`].
+pub fn sample_volume<N: Noise3 + ?Sized>(
+    noise: &N,
+    resolution: (usize, usize, usize),
+) -> Array3<f32> {
+    let (depth, height, width) = resolution;
+    Array3::from_shape_fn(resolution, |(k, i, j)| {
+        noise.sample(
+            j as f32 / width as f32,
+            i as f32 / height as f32,
+            k as f32 / depth as f32,
+        )
+    })
+}
+
+/// Gradients of a 3D noise function on the same grid as [`sample_volume`].
+pub fn gradient_volume<N: Noise3 + ?Sized>(
+    noise: &N,
+    resolution: (usize, usize, usize),
+) -> Array3<Vector3<f32>> {
+    let (depth, height, width) = resolution;
+    Array3::from_shape_fn(resolution, |(k, i, j)| {
+        noise.gradient(
+            j as f32 / width as f32,
+            i as f32 / height as f32,
+            k as f32 / depth as f32,
+        )
+    })
+}
+
+/// Samples a 1D noise function over the unit interval at `length` points, where element `i` is at `x = i / length`.
+pub fn sample_line<N: Noise1 + ?Sized>(noise: &N, length: usize) -> Array1<f32> {
+    Array1::from_shape_fn(length, |i| noise.sample(i as f32 / length as f32))
+}
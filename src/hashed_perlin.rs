@@ -0,0 +1,213 @@
+use std::f32::consts::TAU;
+
+use nalgebra::Vector2;
+use rand::Rng;
+
+use crate::{
+    float::Float,
+    noise::{Noise, Noise64},
+    perlin::{fade, fade_derivative},
+    seed,
+};
+
+/// Perlin noise with gradients hashed from the lattice coordinates instead of stored in an array.
+///
+/// By default the lattice is unbounded, so the noise never repeats and takes no extra memory however far it is sampled,
+/// which suits coordinates in world units such as metres.
+/// The [`tileable`](HashedPerlin::tileable) constructors instead wrap the lattice to tile the unit square like [`Perlin`](crate::Perlin).
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HashedPerlin {
+    scale: (f32, f32),
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, deserialize_with = "deserialize_period")
+    )]
+    period: Option<(u32, u32)>,
+    key: u64,
+}
+
+impl HashedPerlin {
+    /// `scale` is the number of lattice cells per unit of input.
+    pub fn new<R: Rng>(scale: f32, mut rng: R) -> Self {
+        let key = seed::key(&mut rng);
+        Self {
+            scale: (scale, scale),
+            period: None,
+            key,
+        }
+    }
+
+    /// Creates a hashed Perlin noise generator which is reproducible from the given seed.
+    pub fn from_seed(scale: f32, seed: u64) -> Self {
+        Self::new(scale, seed::rng(seed))
+    }
+
+    /// Tiles the unit square with a lattice of `shape` cells along x and y, wrapping the lattice coordinates before hashing.
+    ///
+    /// Away from the last column and row of cells, whose far corners wrap back to the start of the lattice,
+    /// this matches the unbounded noise from the same seed at the same scale.
+    ///
+    /// # Panics
+    ///
+    /// If either side of `shape` is zero or does not fit in a `u32`.
+    pub fn tileable<R: Rng>(shape: (usize, usize), mut rng: R) -> Self {
+        let (Ok(width), Ok(height)) = (u32::try_from(shape.0), u32::try_from(shape.1)) else {
+            panic!("lattice shape {:?} does not fit in u32", shape);
+        };
+        assert!(width > 0 && height > 0);
+
+        let key = seed::key(&mut rng);
+        Self {
+            scale: (width as f32, height as f32),
+            period: Some((width, height)),
+            key,
+        }
+    }
+
+    /// Creates a tileable hashed Perlin noise generator which is reproducible from the given seed.
+    pub fn tileable_from_seed(shape: (usize, usize), seed: u64) -> Self {
+        Self::tileable(shape, seed::rng(seed))
+    }
+
+    // Unit gradient vector at a lattice point.
+    fn lattice(&self, x: i64, y: i64) -> Vector2<f32> {
+        let (x, y) = match self.period {
+            Some((width, height)) => (x.rem_euclid(width as i64), y.rem_euclid(height as i64)),
+            None => (x, y),
+        };
+        let (unit, _) = seed::hash_units(seed::hash2(self.key, x, y));
+        let angle = TAU * unit;
+        Vector2::new(angle.cos(), angle.sin())
+    }
+
+    // Value and gradient together, as each corner contributes to both.
+    fn evaluate<F: Float>(&self, x: F, y: F) -> (F, Vector2<F>) {
+        let scale = Vector2::new(F::cast(self.scale.0 as f64), F::cast(self.scale.1 as f64));
+        let p = Vector2::new(x, y).component_mul(&scale);
+        let cell = p.map(|c| c.floor());
+        let local = p - cell;
+        let (ix, iy) = (cell.x.to_f64() as i64, cell.y.to_f64() as i64);
+
+        let weight = local.map(fade);
+        let weight_derivative = local.map(fade_derivative);
+
+        let one = F::one();
+        let mut value = F::zero();
+        let mut gradient = Vector2::zeros();
+        for corner in 0..4 {
+            let (cx, cy) = (corner & 1, corner >> 1);
+            let vector = self.lattice(ix + cx, iy + cy).map(|c| F::cast(c as f64));
+            let offset = Vector2::new(F::cast(cx as f64), F::cast(cy as f64));
+            let dot = vector.dot(&(local - offset));
+
+            // Bilinear weights use t on the far side of the cell and 1 - t on the near side
+            let (wx, dwx) = if cx == 0 {
+                (one - weight.x, -weight_derivative.x)
+            } else {
+                (weight.x, weight_derivative.x)
+            };
+            let (wy, dwy) = if cy == 0 {
+                (one - weight.y, -weight_derivative.y)
+            } else {
+                (weight.y, weight_derivative.y)
+            };
+
+            value += wx * wy * dot;
+            gradient += vector * (wx * wy) + Vector2::new(dwx * wy, wx * dwy) * dot;
+        }
+
+        // Chain rule: the cell coordinates are scaled by the number of cells per unit
+        (value, gradient.component_mul(&scale))
+    }
+}
+
+impl Noise for HashedPerlin {
+    fn sample(&self, x: f32, y: f32) -> f32 {
+        self.evaluate(x, y).0
+    }
+
+    fn gradient(&self, x: f32, y: f32) -> Vector2<f32> {
+        self.evaluate(x, y).1
+    }
+}
+
+impl Noise64 for HashedPerlin {
+    fn sample_f64(&self, x: f64, y: f64) -> f64 {
+        self.evaluate(x, y).0
+    }
+
+    fn gradient_f64(&self, x: f64, y: f64) -> Vector2<f64> {
+        self.evaluate(x, y).1
+    }
+}
+
+// Rejects a zero period, which would leave no lattice points to wrap onto.
+#[cfg(feature = "serde")]
+fn deserialize_period<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<(u32, u32)>, D::Error> {
+    use serde::{de::Error, Deserialize};
+
+    let period = Option::<(u32, u32)>::deserialize(deserializer)?;
+    if let Some((width, height)) = period {
+        if width == 0 || height == 0 {
+            return Err(D::Error::custom(format!(
+                "period ({}, {}) has no lattice points",
+                width, height
+            )));
+        }
+    }
+    Ok(period)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Points spread over the unit square
+    fn points() -> impl Iterator<Item = (f32, f32)> {
+        (0..500).map(|i| {
+            (
+                (0.618_034 * i as f32) % 1.0,
+                (0.414_214 * i as f32 + 0.1) % 1.0,
+            )
+        })
+    }
+
+    #[test]
+    fn tileable_repeats_over_the_period() {
+        let noise = HashedPerlin::tileable_from_seed((3, 5), 1);
+        for (x, y) in points() {
+            let sample = noise.sample(x, y);
+            let gradient = noise.gradient(x, y);
+            for (dx, dy) in [(1.0, 0.0), (0.0, 1.0), (-2.0, 3.0)] {
+                assert!((noise.sample(x + dx, y + dy) - sample).abs() < 1e-4);
+                assert!((noise.gradient(x + dx, y + dy) - gradient).norm() < 1e-3);
+            }
+        }
+    }
+
+    #[test]
+    fn tileable_matches_unbounded_within_the_first_period() {
+        let tileable = HashedPerlin::tileable_from_seed((4, 4), 1);
+        let unbounded = HashedPerlin::from_seed(4.0, 1);
+        let mut differs = false;
+        for (x, y) in points() {
+            if x < 0.75 && y < 0.75 {
+                assert_eq!(tileable.sample(x, y), unbounded.sample(x, y));
+                assert_eq!(tileable.gradient(x, y), unbounded.gradient(x, y));
+            } else {
+                differs |= tileable.sample(x, y) != unbounded.sample(x, y);
+            }
+        }
+        // The last column and row of cells wrap onto the first lattice points instead
+        assert!(differs);
+    }
+
+    #[test]
+    #[should_panic(expected = "does not fit in u32")]
+    #[cfg(target_pointer_width = "64")]
+    fn tileable_rejects_shapes_beyond_u32() {
+        HashedPerlin::tileable_from_seed((1 << 32, 4), 1);
+    }
+}
@@ -0,0 +1,117 @@
+use std::cmp::Ordering;
+
+use nalgebra::Vector2;
+use rand::Rng;
+
+use crate::{
+    float::Float,
+    noise::{Noise, Noise64},
+    seed,
+};
+
+/// Worley noise with one feature point per lattice cell, placed by hashing the cell coordinates.
+///
+/// Like [`HashedPerlin`](crate::HashedPerlin) the lattice is unbounded, so coordinates can range freely.
+/// Distances are measured in cells, which keeps the value roughly within [0, 1] at any scale.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HashedWorley {
+    scale: f32,
+    key: u64,
+}
+
+impl HashedWorley {
+    /// `scale` is the number of cells, and so feature points, per unit of input.
+    pub fn new<R: Rng>(scale: f32, mut rng: R) -> Self {
+        let key = seed::key(&mut rng);
+        Self { scale, key }
+    }
+
+    /// Creates a hashed Worley noise generator which is reproducible from the given seed.
+    pub fn from_seed(scale: f32, seed: u64) -> Self {
+        Self::new(scale, seed::rng(seed))
+    }
+
+    // Feature point of a cell, relative to its lower corner.
+    fn feature_point(&self, x: i64, y: i64) -> Vector2<f32> {
+        let (u, v) = seed::hash_units(seed::hash2(self.key, x, y));
+        Vector2::new(u, v)
+    }
+
+    // Offset from the nearest feature point, in cells.
+    fn nearest<F: Float>(&self, x: F, y: F) -> Vector2<F> {
+        let p = Vector2::new(x, y) * F::cast(self.scale as f64);
+        let cell = p.map(|c| c.floor());
+        let local = p - cell;
+        let (ix, iy) = (cell.x.to_f64() as i64, cell.y.to_f64() as i64);
+
+        let search = |radius: i64| {
+            (-radius..=radius)
+                .flat_map(|dy| (-radius..=radius).map(move |dx| (dx, dy)))
+                .map(|(dx, dy)| {
+                    let point = self
+                        .feature_point(ix + dx, iy + dy)
+                        .map(|c| F::cast(c as f64));
+                    local - (Vector2::new(F::cast(dx as f64), F::cast(dy as f64)) + point)
+                })
+                .min_by(|a, b| {
+                    a.norm_squared()
+                        .partial_cmp(&b.norm_squared())
+                        .unwrap_or(Ordering::Equal)
+                })
+                .unwrap()
+        };
+
+        // The neighbouring cells hold the nearest point unless it is further away than the edge of the 3x3 block.
+        // The sample's own point is always within sqrt(2), so a 5x5 block is enough otherwise.
+        let nearest = search(1);
+        let (one, two) = (F::one(), F::cast(2.0));
+        let edge = (local.x + one)
+            .min(two - local.x)
+            .min(local.y + one)
+            .min(two - local.y);
+        if nearest.norm_squared() > edge * edge {
+            search(2)
+        } else {
+            nearest
+        }
+    }
+}
+
+impl HashedWorley {
+    /// Distance from the nearest feature point, in cells.
+    fn sample_generic<F: Float>(&self, x: F, y: F) -> F {
+        self.nearest(x, y).norm()
+    }
+
+    /// The unit vector pointing away from the nearest feature point, scaled by the number of cells per unit.
+    /// This is exact everywhere except where two feature points are equally near.
+    fn gradient_generic<F: Float>(&self, x: F, y: F) -> Vector2<F> {
+        let offset = self.nearest(x, y);
+        let distance = offset.norm();
+        if distance > F::zero() {
+            offset * (F::cast(self.scale as f64) / distance)
+        } else {
+            Vector2::zeros()
+        }
+    }
+}
+
+impl Noise for HashedWorley {
+    fn sample(&self, x: f32, y: f32) -> f32 {
+        self.sample_generic(x, y)
+    }
+
+    fn gradient(&self, x: f32, y: f32) -> Vector2<f32> {
+        self.gradient_generic(x, y)
+    }
+}
+
+impl Noise64 for HashedWorley {
+    fn sample_f64(&self, x: f64, y: f64) -> f64 {
+        self.sample_generic(x, y)
+    }
+
+    fn gradient_f64(&self, x: f64, y: f64) -> Vector2<f64> {
+        self.gradient_generic(x, y)
+    }
+}
@@ -0,0 +1,312 @@
+use nalgebra::Vector2;
+use ndarray::Array2;
+
+use crate::{boundary::Boundary, noise::Noise, seed};
+
+/// Particle-based hydraulic erosion.
+///
+/// Each droplet starts at a random point, rolls downhill with some inertia, picks up sediment while it
+/// is fast and has spare capacity, and deposits it when it slows down or climbs. Water evaporates as it goes.
+/// Distances are measured in grid cells and heights in the units of the heightmap.
+#[derive(Clone, Debug)]
+pub struct HydraulicErosion {
+    /// Number of droplets to simulate.
+    pub droplets: usize,
+    /// Maximum number of steps taken by each droplet.
+    pub max_lifetime: usize,
+    /// Fraction of the previous direction kept at each step, in [0, 1].
+    pub inertia: f32,
+    /// Sediment carried per unit of height drop, speed and water.
+    pub sediment_capacity: f32,
+    /// Lower bound on the capacity, so droplets on flat ground still erode a little.
+    pub min_sediment_capacity: f32,
+    /// Fraction of the spare capacity eroded at each step.
+    pub erode_speed: f32,
+    /// Fraction of the excess sediment deposited at each step.
+    pub deposit_speed: f32,
+    /// Fraction of water lost at each step.
+    pub evaporate_speed: f32,
+    /// Acceleration of droplets as they descend.
+    pub gravity: f32,
+    pub initial_water: f32,
+    pub initial_speed: f32,
+    /// Radius, in cells, over which erosion is spread.
+    pub erosion_radius: f32,
+    pub boundary: Boundary,
+}
+
+impl Default for HydraulicErosion {
+    fn default() -> Self {
+        Self {
+            droplets: 50_000,
+            max_lifetime: 30,
+            inertia: 0.05,
+            sediment_capacity: 4.0,
+            min_sediment_capacity: 0.01,
+            erode_speed: 0.3,
+            deposit_speed: 0.3,
+            evaporate_speed: 0.01,
+            gravity: 4.0,
+            initial_water: 1.0,
+            initial_speed: 1.0,
+            erosion_radius: 3.0,
+            boundary: Boundary::Clamped,
+        }
+    }
+}
+
+impl HydraulicErosion {
+    /// Erodes a heightmap in place. The same seed always produces the same result.
+    pub fn erode(&self, heights: &mut Array2<f32>, seed: u64) {
+        self.simulate::<dyn Noise>(heights, None, seed);
+    }
+
+    /// Erodes a heightmap sampled from `noise` with [`sample_grid`](crate::sample_grid),
+    /// taking the first step of each droplet along the steepest descent of the noise's analytic gradient.
+    pub fn erode_with_gradient<N: Noise + ?Sized>(
+        &self,
+        heights: &mut Array2<f32>,
+        noise: &N,
+        seed: u64,
+    ) {
+        self.simulate(heights, Some(noise), seed);
+    }
+
+    fn simulate<N: Noise + ?Sized>(&self, heights: &mut Array2<f32>, noise: Option<&N>, seed: u64) {
+        let (rows, columns) = heights.dim();
+        assert!(rows >= 2 && columns >= 2);
+
+        let mut rng = seed::rng(seed);
+        let brush = Brush::new(self.erosion_radius);
+
+        // Droplets on a clamped grid need a full cell to interpolate over
+        let (span_x, span_y) = match self.boundary {
+            Boundary::Clamped => ((columns - 1) as f32, (rows - 1) as f32),
+            Boundary::Periodic => (columns as f32, rows as f32),
+        };
+
+        for _ in 0..self.droplets {
+            let mut position =
+                Vector2::new(seed::unit(&mut rng) * span_x, seed::unit(&mut rng) * span_y);
+            // The first step follows the analytic gradient alone, as blending it with the sampled
+            // gradient at a low inertia would all but discard it
+            let mut start = noise.map(|noise| {
+                let gradient =
+                    noise.gradient(position.x / columns as f32, position.y / rows as f32);
+                -Vector2::new(gradient.x / columns as f32, gradient.y / rows as f32)
+            });
+            let mut direction = Vector2::zeros();
+            let mut speed = self.initial_speed;
+            let mut water = self.initial_water;
+            let mut sediment = 0.0;
+
+            for _ in 0..self.max_lifetime {
+                let Some((height, gradient)) = self.interpolate(heights, position) else {
+                    break;
+                };
+
+                direction = match start.take() {
+                    Some(start) => start,
+                    None => direction * self.inertia - gradient * (1.0 - self.inertia),
+                };
+                let length = direction.norm();
+                if length == 0.0 {
+                    // Pick a random direction on perfectly flat ground
+                    let angle = std::f32::consts::TAU * seed::unit(&mut rng);
+                    direction = Vector2::new(angle.cos(), angle.sin());
+                } else {
+                    direction /= length;
+                }
+
+                let previous = position;
+                position += direction;
+                if self.boundary == Boundary::Periodic {
+                    position.x = position.x.rem_euclid(columns as f32);
+                    position.y = position.y.rem_euclid(rows as f32);
+                }
+                let Some((new_height, _)) = self.interpolate(heights, position) else {
+                    break;
+                };
+                let delta = new_height - height;
+
+                let capacity = (-delta * speed * water * self.sediment_capacity)
+                    .max(self.min_sediment_capacity);
+
+                if sediment > capacity || delta > 0.0 {
+                    // Fill the pit when climbing, otherwise drop a fraction of the excess
+                    let amount = if delta > 0.0 {
+                        delta.min(sediment)
+                    } else {
+                        (sediment - capacity) * self.deposit_speed
+                    };
+                    sediment -= amount;
+                    self.deposit(heights, previous, amount);
+                } else {
+                    // Never erode more than the height drop, to avoid digging holes
+                    let amount = ((capacity - sediment) * self.erode_speed).min(-delta);
+                    sediment += self.erode_brush(heights, previous, amount, new_height, &brush);
+                }
+
+                speed = (speed * speed - delta * self.gravity).max(0.0).sqrt();
+                water *= 1.0 - self.evaporate_speed;
+            }
+        }
+    }
+
+    // Corner cells of the grid square containing `position`, with the fractional offset within it.
+    fn corners(
+        &self,
+        (rows, columns): (usize, usize),
+        position: Vector2<f32>,
+    ) -> Option<(Corners, f32, f32)> {
+        let x = position.x.floor();
+        let y = position.y.floor();
+        let (i, j) = (y as isize, x as isize);
+        let i0 = self.boundary.index(i, rows)?;
+        let j0 = self.boundary.index(j, columns)?;
+        let i1 = self.boundary.index(i + 1, rows)?;
+        let j1 = self.boundary.index(j + 1, columns)?;
+        Some((
+            [(i0, j0), (i0, j1), (i1, j0), (i1, j1)],
+            position.x - x,
+            position.y - y,
+        ))
+    }
+
+    // Bilinear height and gradient, in cells, at `position`.
+    fn interpolate(
+        &self,
+        heights: &Array2<f32>,
+        position: Vector2<f32>,
+    ) -> Option<(f32, Vector2<f32>)> {
+        let ([c00, c10, c01, c11], fx, fy) = self.corners(heights.dim(), position)?;
+        let (h00, h10, h01, h11) = (heights[c00], heights[c10], heights[c01], heights[c11]);
+
+        let height = h00 * (1.0 - fx) * (1.0 - fy)
+            + h10 * fx * (1.0 - fy)
+            + h01 * (1.0 - fx) * fy
+            + h11 * fx * fy;
+        let gradient = Vector2::new(
+            (h10 - h00) * (1.0 - fy) + (h11 - h01) * fy,
+            (h01 - h00) * (1.0 - fx) + (h11 - h10) * fx,
+        );
+        Some((height, gradient))
+    }
+
+    // Deposits sediment onto the four corners around `position`, weighted bilinearly.
+    fn deposit(&self, heights: &mut Array2<f32>, position: Vector2<f32>, amount: f32) {
+        if let Some(([c00, c10, c01, c11], fx, fy)) = self.corners(heights.dim(), position) {
+            heights[c00] += amount * (1.0 - fx) * (1.0 - fy);
+            heights[c10] += amount * fx * (1.0 - fy);
+            heights[c01] += amount * (1.0 - fx) * fy;
+            heights[c11] += amount * fx * fy;
+        }
+    }
+
+    // Removes up to `amount` of material spread over the brush around `position`, without lowering any cell
+    // below `floor`, and returns how much was taken.
+    fn erode_brush(
+        &self,
+        heights: &mut Array2<f32>,
+        position: Vector2<f32>,
+        amount: f32,
+        floor: f32,
+        brush: &Brush,
+    ) -> f32 {
+        let dim = heights.dim();
+        let centre = (position.y.floor() as usize, position.x.floor() as usize);
+        let cells: Vec<((usize, usize), f32)> = brush
+            .offsets
+            .iter()
+            .filter_map(|&(offset, weight)| {
+                Some((self.boundary.neighbour(dim, centre, offset)?, weight))
+            })
+            .collect();
+        let total: f32 = cells.iter().map(|(_, weight)| weight).sum();
+
+        let mut eroded = 0.0;
+        for (cell, weight) in cells {
+            let take = (amount * weight / total).min((heights[cell] - floor).max(0.0));
+            heights[cell] -= take;
+            eroded += take;
+        }
+        eroded
+    }
+}
+
+// Grid cells at the four corners of a square, ordered (0, 0), (1, 0), (0, 1), (1, 1) in (x, y).
+type Corners = [(usize, usize); 4];
+
+// Cells within the erosion radius, weighted by their distance from the centre.
+struct Brush {
+    offsets: Vec<((isize, isize), f32)>,
+}
+
+impl Brush {
+    fn new(radius: f32) -> Self {
+        let reach = radius.ceil() as isize;
+        let mut offsets = Vec::new();
+        for di in -reach..=reach {
+            for dj in -reach..=reach {
+                let distance = ((di * di + dj * dj) as f32).sqrt();
+                if distance < radius || (di, dj) == (0, 0) {
+                    offsets.push(((di, dj), (radius - distance).max(1e-3)));
+                }
+            }
+        }
+        Self { offsets }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{sample_grid, Perlin, ScaleBias};
+
+    fn terrain() -> ScaleBias {
+        ScaleBias::new(Box::new(Perlin::from_seed((4, 4), 1)), 20.0, 0.0)
+    }
+
+    fn erode(noise: &ScaleBias, boundary: Boundary, seed: u64) -> Array2<f32> {
+        let mut heights = sample_grid(noise, (64, 64));
+        let erosion = HydraulicErosion {
+            droplets: 5000,
+            boundary,
+            ..Default::default()
+        };
+        erosion.erode_with_gradient(&mut heights, noise, seed);
+        heights
+    }
+
+    // Largest mean absolute difference between neighbouring columns, and the same across the wrap.
+    fn column_steps(heights: &Array2<f32>) -> (f32, f32) {
+        let columns = heights.ncols();
+        let step = |a: usize, b: usize| {
+            let difference = &heights.column(a) - &heights.column(b);
+            difference.mapv(f32::abs).mean().unwrap()
+        };
+        let interior = (1..columns).map(|j| step(j - 1, j)).fold(0.0, f32::max);
+        (interior, step(columns - 1, 0))
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_result() {
+        let noise = terrain();
+        let first = erode(&noise, Boundary::Clamped, 7);
+        assert_eq!(first, erode(&noise, Boundary::Clamped, 7));
+        assert_ne!(first, erode(&noise, Boundary::Clamped, 8));
+    }
+
+    #[test]
+    fn periodic_erosion_keeps_tileable_input_tileable() {
+        let noise = terrain();
+        let heights = erode(&noise, Boundary::Periodic, 7);
+        assert_ne!(heights, sample_grid(&noise, (64, 64)));
+
+        // The wrap is no rougher than any step inside the grid, along either axis
+        let (interior, seam) = column_steps(&heights);
+        assert!(seam <= interior, "seam {} interior {}", seam, interior);
+        let (interior, seam) = column_steps(&heights.t().to_owned());
+        assert!(seam <= interior, "seam {} interior {}", seam, interior);
+    }
+}
@@ -0,0 +1,417 @@
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::BinaryHeap,
+    f32::consts::{FRAC_PI_4, SQRT_2, TAU},
+};
+
+use ndarray::Array2;
+
+use crate::{boundary::Boundary, noise::Noise};
+
+// Neighbour offsets in bearing order, clockwise from north (decreasing row index).
+const NEIGHBOURS: [(isize, isize); 8] = [
+    (-1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+    (-1, -1),
+];
+
+/// Raises every depression in a heightmap until it spills, so that water can drain from every cell.
+///
+/// Filled areas slope very slightly towards their spill point, so flow directions are defined everywhere.
+/// With [`Boundary::Clamped`] water drains off the edges of the grid; a periodic grid has no edges,
+/// so everything drains towards its lowest cell instead.
+pub fn fill_depressions(heights: &Array2<f32>, boundary: Boundary) -> Array2<f32> {
+    let dim = heights.dim();
+    let (rows, columns) = dim;
+    let mut filled = heights.clone();
+    let mut closed = Array2::from_elem(dim, false);
+    let mut open = BinaryHeap::new();
+
+    // Priority-flood outwards from the outlets, lowest first
+    let outlets: Vec<(usize, usize)> = match boundary {
+        Boundary::Clamped => heights
+            .indexed_iter()
+            .map(|(cell, _)| cell)
+            .filter(|&(i, j)| i == 0 || j == 0 || i == rows - 1 || j == columns - 1)
+            .collect(),
+        Boundary::Periodic => heights
+            .indexed_iter()
+            .min_by(|a, b| a.1.total_cmp(b.1))
+            .map(|(cell, _)| cell)
+            .into_iter()
+            .collect(),
+    };
+    for cell in outlets {
+        closed[cell] = true;
+        open.push(Reverse(Cell {
+            height: heights[cell],
+            cell,
+        }));
+    }
+
+    while let Some(Reverse(Cell { height, cell })) = open.pop() {
+        for offset in NEIGHBOURS {
+            let Some(neighbour) = boundary.neighbour(dim, cell, offset) else {
+                continue;
+            };
+            if !closed[neighbour] {
+                closed[neighbour] = true;
+                filled[neighbour] = filled[neighbour].max(next_up(height));
+                open.push(Reverse(Cell {
+                    height: filled[neighbour],
+                    cell: neighbour,
+                }));
+            }
+        }
+    }
+
+    filled
+}
+
+/// Direction water leaves each cell of a heightmap.
+#[derive(Clone, Debug)]
+pub enum FlowDirections {
+    /// The neighbour each cell drains into, or None at sinks and where water leaves the grid.
+    D8(Array2<Option<(usize, usize)>>),
+    /// Bearing of flow in radians clockwise from north (up the image), as for
+    /// [`TerrainAttributes::aspect`](crate::TerrainAttributes::aspect), or NaN where there is no downhill direction.
+    /// Flow is shared between the two neighbours either side of the bearing.
+    DInfinity(Array2<f32>),
+}
+
+impl FlowDirections {
+    /// Sends all flow from each cell to the neighbour of steepest descent, among the eight surrounding it.
+    pub fn d8(heights: &Array2<f32>, boundary: Boundary) -> Self {
+        Self::D8(Array2::from_shape_fn(heights.dim(), |cell| {
+            steepest_neighbour(heights, boundary, cell)
+        }))
+    }
+
+    /// Finds the steepest downhill bearing over the eight triangular facets around each cell (Tarboton, 1997).
+    pub fn d_infinity(heights: &Array2<f32>, boundary: Boundary) -> Self {
+        let dim = heights.dim();
+        Self::DInfinity(Array2::from_shape_fn(dim, |cell| {
+            let height = heights[cell];
+            let mut steepest = 0.0;
+            let mut bearing = f32::NAN;
+
+            for k in 0..8_usize {
+                // Each facet spans from a cardinal neighbour to the next diagonal one, in either direction
+                let (cardinal, diagonal, start, sign) = if k % 2 == 0 {
+                    (k, k + 1, k, 1.0)
+                } else {
+                    ((k + 1) % 8, k, k + 1, -1.0)
+                };
+                let (Some(c1), Some(c2)) = (
+                    boundary.neighbour(dim, cell, NEIGHBOURS[cardinal]),
+                    boundary.neighbour(dim, cell, NEIGHBOURS[diagonal]),
+                ) else {
+                    continue;
+                };
+                let s1 = height - heights[c1];
+                let s2 = heights[c1] - heights[c2];
+
+                let mut angle = s2.atan2(s1);
+                let mut slope = s1.hypot(s2);
+                if angle < 0.0 {
+                    angle = 0.0;
+                    slope = s1;
+                } else if angle > FRAC_PI_4 {
+                    angle = FRAC_PI_4;
+                    slope = (height - heights[c2]) / SQRT_2;
+                }
+
+                if slope > steepest {
+                    steepest = slope;
+                    bearing = (start as f32 * FRAC_PI_4 + sign * angle).rem_euclid(TAU);
+                }
+            }
+            bearing
+        }))
+    }
+
+    /// Takes D-infinity bearings from the analytic gradient of a noise, on the same grid as [`sample_grid`](crate::sample_grid).
+    ///
+    /// Where these point uphill in the heightmap being routed, such as across filled depressions,
+    /// [`accumulation`](Self::accumulation) falls back to the steepest downhill neighbour.
+    pub fn d_infinity_from_gradient<N: Noise + ?Sized>(
+        noise: &N,
+        resolution: (usize, usize),
+    ) -> Self {
+        let (height, width) = resolution;
+        Self::DInfinity(Array2::from_shape_fn(resolution, |(i, j)| {
+            let u = j as f32 / width as f32;
+            let v = i as f32 / height as f32;
+            // Slopes per cell, so the bearing is measured on the grid
+            let gradient = noise.gradient(u, v);
+            let (p, q) = (gradient.x / width as f32, gradient.y / height as f32);
+            if p == 0.0 && q == 0.0 {
+                f32::NAN
+            } else {
+                (-p).atan2(q).rem_euclid(TAU)
+            }
+        }))
+    }
+
+    /// Number of cells draining through each cell, including itself.
+    ///
+    /// Flow only ever moves to strictly lower cells, so `heights` should normally come from [`fill_depressions`],
+    /// otherwise water collects in every pit and flat.
+    pub fn accumulation(&self, heights: &Array2<f32>, boundary: Boundary) -> Array2<f32> {
+        let dim = heights.dim();
+        let mut accumulation = Array2::from_elem(dim, 1.0);
+
+        // Visiting cells from highest to lowest guarantees all inflow has arrived before a cell passes it on
+        let mut order: Vec<(usize, usize)> = heights.indexed_iter().map(|(cell, _)| cell).collect();
+        order.sort_by(|&a, &b| heights[b].total_cmp(&heights[a]));
+
+        for cell in order {
+            let flow = accumulation[cell];
+            match self {
+                FlowDirections::D8(receivers) => {
+                    if let Some(receiver) = receivers[cell] {
+                        if heights[receiver] < heights[cell] {
+                            accumulation[receiver] += flow;
+                        }
+                    }
+                }
+                FlowDirections::DInfinity(bearings) => {
+                    let shares = d_infinity_shares(heights, boundary, cell, bearings[cell]);
+                    if let Some(shares) = shares {
+                        for (receiver, share) in shares {
+                            if let Some(receiver) = receiver {
+                                accumulation[receiver] += flow * share;
+                            }
+                        }
+                    } else if let Some(receiver) = steepest_neighbour(heights, boundary, cell) {
+                        accumulation[receiver] += flow;
+                    }
+                }
+            }
+        }
+
+        accumulation
+    }
+}
+
+/// Marks cells draining at least `threshold` cells as part of the river network.
+pub fn river_mask(accumulation: &Array2<f32>, threshold: f32) -> Array2<bool> {
+    accumulation.mapv(|flow| flow >= threshold)
+}
+
+// Receiving neighbours and the fraction of flow each gets, where None is off the edge of the grid.
+type Shares = [(Option<(usize, usize)>, f32); 2];
+
+// Splits flow between the two neighbours either side of `bearing`, in proportion to the angle,
+// dropping any share that would go uphill. None means no share can be used.
+fn d_infinity_shares(
+    heights: &Array2<f32>,
+    boundary: Boundary,
+    cell: (usize, usize),
+    bearing: f32,
+) -> Option<Shares> {
+    if bearing.is_nan() {
+        return None;
+    }
+    let sector = bearing / FRAC_PI_4;
+    let k = sector as usize % 8;
+    let fraction = sector.fract();
+    let shares = [(k, 1.0 - fraction), ((k + 1) % 8, fraction)].map(|(k, share)| {
+        let receiver = boundary.neighbour(heights.dim(), cell, NEIGHBOURS[k]);
+        // Water flowing off a clamped edge leaves the grid
+        let usable = receiver.map_or(true, |receiver| heights[receiver] < heights[cell]);
+        (receiver, if usable { share } else { 0.0 })
+    });
+
+    let total = shares[0].1 + shares[1].1;
+    (total > 0.0).then(|| shares.map(|(receiver, share)| (receiver, share / total)))
+}
+
+// Lower neighbour with the steepest drop, if there is one.
+fn steepest_neighbour(
+    heights: &Array2<f32>,
+    boundary: Boundary,
+    cell: (usize, usize),
+) -> Option<(usize, usize)> {
+    let mut steepest = 0.0;
+    let mut receiver = None;
+    for (k, offset) in NEIGHBOURS.into_iter().enumerate() {
+        if let Some(neighbour) = boundary.neighbour(heights.dim(), cell, offset) {
+            let slope = (heights[cell] - heights[neighbour]) / distance(k);
+            if slope > steepest {
+                steepest = slope;
+                receiver = Some(neighbour);
+            }
+        }
+    }
+    receiver
+}
+
+// Smallest f32 greater than x, as `f32::next_up` does from Rust 1.86.
+fn next_up(x: f32) -> f32 {
+    if x.is_nan() || x == f32::INFINITY {
+        x
+    } else if x == 0.0 {
+        f32::from_bits(1)
+    } else if x > 0.0 {
+        f32::from_bits(x.to_bits() + 1)
+    } else {
+        f32::from_bits(x.to_bits() - 1)
+    }
+}
+
+fn distance(k: usize) -> f32 {
+    if k % 2 == 0 {
+        1.0
+    } else {
+        SQRT_2
+    }
+}
+
+// Grid cell ordered by height, for the priority queue.
+struct Cell {
+    height: f32,
+    cell: (usize, usize),
+}
+
+impl PartialEq for Cell {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Cell {}
+
+impl PartialOrd for Cell {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Cell {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.height
+            .total_cmp(&other.height)
+            .then(self.cell.cmp(&other.cell))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::array;
+
+    use super::*;
+
+    const WEST: f32 = 1.5 * std::f32::consts::PI;
+
+    // A 5×5 plane rising to the east, z = j, with a pit at the centre.
+    fn tilted_plane_with_pit() -> Array2<f32> {
+        let mut heights = Array2::from_shape_fn((5, 5), |(_, j)| j as f32);
+        heights[(2, 2)] = 0.0;
+        heights
+    }
+
+    fn receivers(directions: &FlowDirections) -> &Array2<Option<(usize, usize)>> {
+        match directions {
+            FlowDirections::D8(receivers) => receivers,
+            FlowDirections::DInfinity(_) => panic!("expected D8"),
+        }
+    }
+
+    fn bearings(directions: &FlowDirections) -> &Array2<f32> {
+        match directions {
+            FlowDirections::DInfinity(bearings) => bearings,
+            FlowDirections::D8(_) => panic!("expected D-infinity"),
+        }
+    }
+
+    #[test]
+    fn pit_on_a_tilted_plane() {
+        let heights = tilted_plane_with_pit();
+        let filled = fill_depressions(&heights, Boundary::Clamped);
+
+        // The pit spills over its lowest neighbours at height 1, and nothing else changes
+        let mut expected = heights.clone();
+        expected[(2, 2)] = next_up(1.0);
+        assert_eq!(filled, expected);
+
+        // Flow runs west and leaves the grid from the first column, except that the cells diagonally
+        // east of the filled pit drop more steeply into it than to their western neighbours
+        let d8 = FlowDirections::d8(&filled, Boundary::Clamped);
+        let d_infinity = FlowDirections::d_infinity(&filled, Boundary::Clamped);
+        for ((i, j), &receiver) in receivers(&d8).indexed_iter() {
+            let bearing = bearings(&d_infinity)[(i, j)];
+            match (i, j) {
+                (_, 0) => {
+                    assert_eq!(receiver, None);
+                    assert!(bearing.is_nan());
+                }
+                (1, 3) => {
+                    assert_eq!(receiver, Some((2, 2)));
+                    assert!((bearing - 5.0 * FRAC_PI_4).abs() < 1e-5);
+                }
+                (3, 3) => {
+                    assert_eq!(receiver, Some((2, 2)));
+                    assert!((bearing - 7.0 * FRAC_PI_4).abs() < 1e-5);
+                }
+                _ => {
+                    assert_eq!(receiver, Some((i, j - 1)));
+                    assert!((bearing - WEST).abs() < 1e-5);
+                }
+            }
+        }
+
+        let expected = array![
+            [5.0, 4.0, 3.0, 2.0, 1.0],
+            [3.0, 2.0, 1.0, 2.0, 1.0],
+            [9.0, 8.0, 7.0, 2.0, 1.0],
+            [3.0, 2.0, 1.0, 2.0, 1.0],
+            [5.0, 4.0, 3.0, 2.0, 1.0],
+        ];
+        assert_eq!(d8.accumulation(&filled, Boundary::Clamped), expected);
+        let accumulation = d_infinity.accumulation(&filled, Boundary::Clamped);
+        assert!((&accumulation - &expected).iter().all(|d| d.abs() < 1e-4));
+
+        // Without filling, the pit collects the flow of the cells around it
+        let accumulation = FlowDirections::d8(&heights, Boundary::Clamped)
+            .accumulation(&heights, Boundary::Clamped);
+        assert!(accumulation[(2, 2)] > 5.0);
+        assert_eq!(accumulation.column(0).sum(), 25.0 - accumulation[(2, 2)]);
+    }
+
+    #[test]
+    fn periodic_flow_crosses_the_edges() {
+        // A ramp rising to the east whose lowest column is 3, so the first column drains across the wrap
+        let heights = Array2::from_shape_fn((5, 5), |(_, j)| ((j + 2) % 5) as f32);
+        let filled = fill_depressions(&heights, Boundary::Periodic);
+
+        // Only the lowest column is raised, just enough to drain along itself to the outlet at (0, 3)
+        for ((i, j), &height) in filled.indexed_iter() {
+            if j == 3 && i != 0 {
+                assert!(height > 0.0 && height < 1e-30);
+            } else {
+                assert_eq!(height, heights[(i, j)]);
+            }
+        }
+
+        let d8 = FlowDirections::d8(&filled, Boundary::Periodic);
+        for i in 0..5 {
+            assert_eq!(receivers(&d8)[(i, 0)], Some((i, 4)));
+        }
+        let d_infinity = FlowDirections::d_infinity(&filled, Boundary::Periodic);
+        assert!((bearings(&d_infinity)[(2, 0)] - WEST).abs() < 1e-5);
+
+        // Every cell drains to the outlet. The second column drains through the first, while the third
+        // drops straight into the lowest column
+        let accumulation = d8.accumulation(&filled, Boundary::Periodic);
+        assert_eq!(accumulation[(0, 3)], 25.0);
+        assert_eq!(accumulation[(2, 0)], 2.0);
+        let accumulation = d_infinity.accumulation(&filled, Boundary::Periodic);
+        assert!((accumulation[(0, 3)] - 25.0).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,71 @@
+use rand::Rng;
+
+use crate::{float::Float, seed};
+
+/// Random values at the integer points of a scaled 1D lattice, shared by the 1D generators.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct Lattice1 {
+    scale: f32,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, deserialize_with = "deserialize_period")
+    )]
+    period: Option<u32>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::seed::serde_perm"))]
+    perm: [u8; 512], // Permutation table repeated twice
+}
+
+impl Lattice1 {
+    pub(crate) fn new<R: Rng>(scale: f32, mut rng: R) -> Self {
+        let perm = seed::permutation(&mut rng);
+        Self {
+            scale,
+            period: None,
+            perm,
+        }
+    }
+
+    // Repeats over the unit interval, rounding the scale to a whole number of lattice cells.
+    pub(crate) fn periodic(mut self) -> Self {
+        let period = self.scale.round().max(1.0);
+        self.scale = period;
+        self.period = Some(period as u32);
+        self
+    }
+
+    // Number of lattice cells per unit of input.
+    pub(crate) fn scale<F: Float>(&self) -> F {
+        F::cast(self.scale as f64)
+    }
+
+    // Lattice cell containing x, and the offset of x within it.
+    pub(crate) fn locate<F: Float>(&self, x: F) -> (i32, F) {
+        let x = x * self.scale();
+        (x.floor().to_f64() as i32, x - x.floor())
+    }
+
+    // Value in [-1, 1] at a lattice point, which Perlin1 and Simplex1 use as a slope
+    pub(crate) fn value<F: Float>(&self, i: i32) -> F {
+        let i = match self.period {
+            Some(period) => i.rem_euclid(period as i32),
+            None => i,
+        };
+        let low = self.perm[(i & 255) as usize] as usize;
+        let hash = self.perm[low + ((i >> 8) & 255) as usize];
+        F::cast((hash as f32 / 127.5 - 1.0) as f64)
+    }
+}
+
+// Rejects a zero period, which would leave no lattice points to wrap onto.
+#[cfg(feature = "serde")]
+fn deserialize_period<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<u32>, D::Error> {
+    use serde::{de::Error, Deserialize};
+
+    let period = Option::<u32>::deserialize(deserializer)?;
+    if period == Some(0) {
+        return Err(D::Error::custom("period 0 has no lattice points"));
+    }
+    Ok(period)
+}
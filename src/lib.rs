@@ -1,13 +1,75 @@
+mod boundary;
+mod combine;
+#[cfg(feature = "config")]
+mod config;
+mod curve;
+#[cfg(feature = "export")]
+mod export;
+mod float;
+mod generator;
+mod grid;
+mod hashed_perlin;
+mod hashed_worley;
+mod hydraulic;
+mod hydrology;
+mod lattice1;
+mod looping;
+mod mesh;
+mod modifier;
 mod noise;
+mod normal_map;
 mod open_simplex;
 mod perlin;
+mod perlin1;
+mod perlin3;
+mod seed;
 mod simplex;
+mod simplex1;
+mod simplex3;
+mod simplex4;
+mod sphere;
 mod stack;
+mod terrain;
+mod thermal;
+mod transform;
+mod turbulence;
+mod value1;
 mod worley;
+mod worley3;
 
-pub use noise::Noise;
+pub use boundary::Boundary;
+pub use combine::{Add, Blend, Max, Min, Multiply, Select, Subtract};
+#[cfg(feature = "config")]
+pub use config::{ConfigError, LayerConfig, NodeConfig};
+pub use curve::{Curve, Terrace};
+#[cfg(feature = "export")]
+pub use export::{save_heightmap, write_heightmap, HeightmapFormat};
+pub use generator::Generator;
+pub use grid::{gradient_grid, gradient_volume, sample_grid, sample_line, sample_volume};
+pub use hashed_perlin::HashedPerlin;
+pub use hashed_worley::HashedWorley;
+pub use hydraulic::HydraulicErosion;
+pub use hydrology::{fill_depressions, river_mask, FlowDirections};
+pub use looping::{Frame, LoopingSimplex};
+pub use mesh::Mesh;
+pub use modifier::{Abs, Clamp, Exponent, Invert, Pow, ScaleBias};
+pub use noise::{Noise, Noise1, Noise1_64, Noise3, Noise3_64, Noise4, Noise4_64, Noise64};
+pub use normal_map::{encode_rgb16, encode_rgb8, normal_map, NormalConvention};
 pub use open_simplex::OpenSimplex;
 pub use perlin::Perlin;
+pub use perlin1::Perlin1;
+pub use perlin3::Perlin3;
+pub use seed::layer_seed;
 pub use simplex::Simplex;
-pub use stack::{GradientFunction, Stack};
+pub use simplex1::Simplex1;
+pub use simplex3::Simplex3;
+pub use simplex4::Simplex4;
+pub use sphere::{CubeFace, CubeGrid, Sphere};
+pub use stack::{GradientFunction, Stack, Stack1, Stack3};
+pub use terrain::{TerrainAttributes, TerrainMaps};
+pub use thermal::ThermalErosion;
+pub use transform::{Affine2, Rotate, ScaleDomain, Translate};
+pub use turbulence::Turbulence;
+pub use value1::Value1;
 pub use worley::Worley;
+pub use worley3::Worley3;
@@ -0,0 +1,263 @@
+use std::{cmp::Ordering, f64::consts::TAU};
+
+use nalgebra::{Vector2, Vector3};
+use rand::Rng;
+
+use crate::{
+    float::Float,
+    noise::{Noise, Noise3, Noise3_64, Noise64},
+    seed,
+};
+
+/// Animated 2D noise which tiles in `u` and `v` and loops over time.
+///
+/// As a [`Noise3`], `sample(u, v, t)` repeats whenever `u`, `v` or `t` changes by one.
+/// Each coordinate is mapped onto its own circle, and 6D simplex noise is sampled on the product of the three circles,
+/// in the same way as [`OpenSimplex`](crate::OpenSimplex) samples 4D noise on a torus to tile in 2D.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LoopingSimplex {
+    scale: f32,
+    time_scale: f32,
+    #[cfg_attr(feature = "serde", serde(with = "crate::seed::serde_perm"))]
+    perm: [u8; 512], // Permutation table repeated twice
+}
+
+impl LoopingSimplex {
+    /// `scale` sets the feature size across the tile, and `time_scale` how far the noise evolves over one loop.
+    pub fn new<R: Rng>(scale: f32, time_scale: f32, mut rng: R) -> Self {
+        let perm = seed::permutation(&mut rng);
+        Self {
+            scale,
+            time_scale,
+            perm,
+        }
+    }
+
+    /// Creates a looping Simplex noise generator which is reproducible from the given seed.
+    pub fn from_seed(scale: f32, time_scale: f32, seed: u64) -> Self {
+        Self::new(scale, time_scale, seed::rng(seed))
+    }
+
+    // Point on the three circles, and the derivative of each 6D coordinate with respect to its own angle.
+    fn embed<F: Float>(&self, u: F, v: F, t: F) -> ([F; 6], [F; 6]) {
+        // Offset from the origin, where every lattice hash would otherwise line up
+        const OFFSET: [f64; 6] = [123.0, 231.0, 312.0, 273.0, 137.0, 319.0];
+
+        let tau = F::cast(TAU);
+        let mut point = [F::zero(); 6];
+        let mut tangent = [F::zero(); 6];
+        for (axis, (value, radius)) in [(u, self.scale), (v, self.scale), (t, self.time_scale)]
+            .into_iter()
+            .enumerate()
+        {
+            let radius = F::cast(radius as f64);
+            let (sin, cos) = (tau * value).sin_cos();
+            point[2 * axis] = F::cast(OFFSET[2 * axis]) + radius * sin;
+            point[2 * axis + 1] = F::cast(OFFSET[2 * axis + 1]) + radius * cos;
+            tangent[2 * axis] = tau * radius * cos;
+            tangent[2 * axis + 1] = -tau * radius * sin;
+        }
+        (point, tangent)
+    }
+
+    fn sample_generic<F: Float>(&self, u: F, v: F, t: F) -> F {
+        let (point, _) = self.embed(u, v, t);
+        simplex6d_with_grad(&self.perm, point).0
+    }
+
+    fn gradient_generic<F: Float>(&self, u: F, v: F, t: F) -> Vector3<F> {
+        let (point, tangent) = self.embed(u, v, t);
+        let (_noise, grad) = simplex6d_with_grad(&self.perm, point);
+        // Chain rule: each of u, v and t only moves its own pair of 6D coordinates
+        Vector3::from_fn(|axis, _| {
+            grad[2 * axis] * tangent[2 * axis] + grad[2 * axis + 1] * tangent[2 * axis + 1]
+        })
+    }
+}
+
+impl Noise3 for LoopingSimplex {
+    fn sample(&self, u: f32, v: f32, t: f32) -> f32 {
+        self.sample_generic(u, v, t)
+    }
+
+    fn gradient(&self, u: f32, v: f32, t: f32) -> Vector3<f32> {
+        self.gradient_generic(u, v, t)
+    }
+}
+
+impl Noise3_64 for LoopingSimplex {
+    fn sample_f64(&self, u: f64, v: f64, t: f64) -> f64 {
+        self.sample_generic(u, v, t)
+    }
+
+    fn gradient_f64(&self, u: f64, v: f64, t: f64) -> Vector3<f64> {
+        self.gradient_generic(u, v, t)
+    }
+}
+
+/// A single frame of animated noise, treating the third coordinate of a [`Noise3`] as time.
+///
+/// This lets frames of [`LoopingSimplex`], or of any noise tileable over the unit cube such as [`Perlin3`](crate::Perlin3),
+/// be used wherever 2D noise is expected.
+pub struct Frame<'a, N: ?Sized = dyn Noise3> {
+    noise: &'a N,
+    time: f32,
+}
+
+impl<'a, N: Noise3 + ?Sized> Frame<'a, N> {
+    pub fn new(noise: &'a N, time: f32) -> Self {
+        Self { noise, time }
+    }
+}
+
+impl<N: Noise3 + ?Sized> Noise for Frame<'_, N> {
+    fn sample(&self, u: f32, v: f32) -> f32 {
+        self.noise.sample(u, v, self.time)
+    }
+
+    fn gradient(&self, u: f32, v: f32) -> Vector2<f32> {
+        self.noise.gradient(u, v, self.time).xy()
+    }
+}
+
+impl<N: Noise3_64 + ?Sized> Noise64 for Frame<'_, N> {
+    fn sample_f64(&self, u: f64, v: f64) -> f64 {
+        self.noise.sample_f64(u, v, self.time as f64)
+    }
+
+    fn gradient_f64(&self, u: f64, v: f64) -> Vector2<f64> {
+        self.noise.gradient_f64(u, v, self.time as f64).xy()
+    }
+}
+
+const DIMENSIONS: usize = 6;
+const SKEW_FACTOR: f64 = 0.274_291_9; // (sqrt(7) - 1) / 6
+const UNSKEW_FACTOR: f64 = 0.103_672_3; // (1 - 1 / sqrt(7)) / 6
+
+// Squared radius of each corner's kernel, small enough to vanish before reaching any simplex it is not part of
+const RADIUS_SQUARED: f64 = 0.5;
+
+// Scales the sum of kernels into roughly [-1, 1]
+const SCALE: f64 = 40.0;
+
+// 6D Simplex noise with analytic gradient.
+fn simplex6d_with_grad<F: Float>(perm: &[u8; 512], point: [F; 6]) -> (F, [F; 6]) {
+    let c = F::cast;
+    let sum = |values: &[F]| values.iter().fold(F::zero(), |total, &value| total + value);
+
+    // Skew input space to determine which simplex cell we're in
+    let s = sum(&point) * c(SKEW_FACTOR);
+    let cell = point.map(|c| (c + s).floor());
+
+    // Unskew back
+    let t = sum(&cell) * c(UNSKEW_FACTOR);
+    let mut offset = [F::zero(); DIMENSIONS];
+    for axis in 0..DIMENSIONS {
+        offset[axis] = point[axis] - (cell[axis] - t);
+    }
+
+    // The simplex steps along the axes in decreasing order of offset
+    let mut order: [usize; DIMENSIONS] = std::array::from_fn(|axis| axis);
+    order.sort_by(|&a, &b| offset[b].partial_cmp(&offset[a]).unwrap_or(Ordering::Equal));
+
+    let mut corner = cell.map(|c| c.to_f64() as i32);
+    let mut value = F::zero();
+    let mut gradient = [F::zero(); DIMENSIONS];
+    for k in 0..=DIMENSIONS {
+        if k > 0 {
+            corner[order[k - 1]] += 1;
+        }
+
+        let mut d = [F::zero(); DIMENSIONS];
+        for axis in 0..DIMENSIONS {
+            let step = (c(corner[axis] as f64) - cell[axis]) - c(k as f64) * c(UNSKEW_FACTOR);
+            d[axis] = offset[axis] - step;
+        }
+
+        let t = c(RADIUS_SQUARED) - sum(&d.map(|c| c * c));
+        if t > F::zero() {
+            let g = lattice_gradient(hash6(perm, corner)).map(|g| c(g as f64));
+            let dot = sum(&std::array::from_fn::<F, DIMENSIONS, _>(|axis| {
+                g[axis] * d[axis]
+            }));
+            let t2 = t * t;
+            let t4 = t2 * t2;
+            value += t4 * dot;
+            // d/dd of t^4 * dot is -8 * t^3 * dot * d + t^4 * g
+            for axis in 0..DIMENSIONS {
+                gradient[axis] += c(-8.0) * t * t2 * dot * d[axis] + t4 * g[axis];
+            }
+        }
+    }
+
+    (c(SCALE) * value, gradient.map(|g| c(SCALE) * g))
+}
+
+// 6D hash function using the permutation table.
+fn hash6(perm: &[u8; 512], corner: [i32; DIMENSIONS]) -> usize {
+    corner
+        .iter()
+        .fold(0, |idx, &c| perm[(idx + (c & 255) as usize) & 511] as usize)
+}
+
+// Gradient with one zero component and the others ±1, generalising the 4D gradient table.
+fn lattice_gradient(hash: usize) -> [f32; DIMENSIONS] {
+    let zero = hash % DIMENSIONS;
+    let mut signs = hash / DIMENSIONS;
+    let mut g = [0.0; DIMENSIONS];
+    for (axis, component) in g.iter_mut().enumerate() {
+        if axis != zero {
+            *component = if signs & 1 == 0 { 1.0 } else { -1.0 };
+            signs >>= 1;
+        }
+    }
+    g
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loops_over_time() {
+        let noise = LoopingSimplex::from_seed(2.0, 1.5, 1);
+        let mut changed = false;
+        for i in 0..200 {
+            let (u, v, t) = (
+                (0.618_034 * i as f32) % 1.0,
+                (0.414_214 * i as f32) % 1.0,
+                (0.732_051 * i as f32) % 1.0,
+            );
+            let sample = noise.sample(u, v, t);
+            let gradient = noise.gradient(u, v, t);
+            for shift in [-1.0, 1.0, 2.0] {
+                assert!((noise.sample(u, v, t + shift) - sample).abs() < 1e-4);
+                assert!(
+                    (noise.gradient(u, v, t + shift) - gradient).norm()
+                        < 1e-2 * gradient.norm().max(1.0)
+                );
+            }
+            changed |= (noise.sample(u, v, t + 0.5) - sample).abs() > 0.1;
+        }
+        // Half a loop later the noise has moved on
+        assert!(changed);
+    }
+
+    #[test]
+    fn f64_matches_f32() {
+        let noise = LoopingSimplex::from_seed(2.0, 1.5, 1);
+        for i in 0..200 {
+            let (u, v, t) = (
+                (0.618_034 * i as f32) % 1.0,
+                (0.414_214 * i as f32) % 1.0,
+                (0.732_051 * i as f32) % 1.0,
+            );
+            let (u64, v64, t64) = (u as f64, v as f64, t as f64);
+            let sample = noise.sample(u, v, t) as f64;
+            assert!((noise.sample_f64(u64, v64, t64) - sample).abs() < 1e-3);
+            let gradient = noise.gradient(u, v, t).cast::<f64>();
+            let error = (noise.gradient_f64(u64, v64, t64) - gradient).norm();
+            assert!(error < 1e-2 * gradient.norm().max(1.0));
+        }
+    }
+}
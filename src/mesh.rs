@@ -0,0 +1,239 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use nalgebra::{Vector2, Vector3};
+
+use crate::noise::Noise;
+
+/// Triangle mesh of a noise heightfield.
+///
+/// Vertices lie at `(u, v, height_scale * noise(u, v))` over the unit square, with z up,
+/// and triangles wind counter-clockwise when viewed from above.
+/// Texture coordinates are `(u, 1 - v)`, so images from [`sample_grid`](crate::sample_grid) map on the right way up.
+pub struct Mesh {
+    pub positions: Vec<Vector3<f32>>,
+    pub normals: Vec<Vector3<f32>>,
+    pub uvs: Vec<Vector2<f32>>,
+    pub triangles: Vec<[u32; 3]>,
+}
+
+impl Mesh {
+    /// Triangulates a grid of `(rows, columns)` vertices spanning the unit square,
+    /// with per-vertex normals taken from the analytic gradient.
+    ///
+    /// When `seamless` is set the last row and column copy the heights and normals of the first,
+    /// so that meshes of a tileable noise placed side by side meet exactly without cracks.
+    pub fn from_noise<N: Noise + ?Sized>(
+        noise: &N,
+        height_scale: f32,
+        resolution: (usize, usize),
+        seamless: bool,
+    ) -> Self {
+        let (rows, columns) = resolution;
+        assert!(rows >= 2 && columns >= 2);
+
+        let vertex_count = rows * columns;
+        let mut positions = Vec::with_capacity(vertex_count);
+        let mut normals = Vec::with_capacity(vertex_count);
+        let mut uvs = Vec::with_capacity(vertex_count);
+
+        for i in 0..rows {
+            for j in 0..columns {
+                let u = j as f32 / (columns - 1) as f32;
+                let v = i as f32 / (rows - 1) as f32;
+
+                // Wrap the far edges back onto the near ones so they are sampled at identical coordinates
+                let (su, sv) = if seamless {
+                    (
+                        (j % (columns - 1)) as f32 / (columns - 1) as f32,
+                        (i % (rows - 1)) as f32 / (rows - 1) as f32,
+                    )
+                } else {
+                    (u, v)
+                };
+
+                let height = height_scale * noise.sample(su, sv);
+                let gradient = noise.gradient(su, sv) * height_scale;
+
+                positions.push(Vector3::new(u, v, height));
+                normals.push(Vector3::new(-gradient.x, -gradient.y, 1.0).normalize());
+                uvs.push(Vector2::new(u, 1.0 - v));
+            }
+        }
+
+        let mut triangles = Vec::with_capacity(2 * (rows - 1) * (columns - 1));
+        for i in 0..rows - 1 {
+            for j in 0..columns - 1 {
+                let a = (i * columns + j) as u32;
+                let b = a + 1;
+                let c = a + columns as u32;
+                let d = c + 1;
+                triangles.push([a, b, d]);
+                triangles.push([a, d, c]);
+            }
+        }
+
+        Self {
+            positions,
+            normals,
+            uvs,
+            triangles,
+        }
+    }
+
+    /// Saves the mesh, choosing Wavefront OBJ, binary STL or binary PLY from the file extension.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_ascii_lowercase());
+
+        let mut writer = BufWriter::new(File::create(path)?);
+        match extension.as_deref() {
+            Some("obj") => self.write_obj(&mut writer)?,
+            Some("stl") => self.write_stl(&mut writer)?,
+            Some("ply") => self.write_ply(&mut writer)?,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("unrecognised mesh extension for {}", path.display()),
+                ))
+            }
+        }
+        writer.flush()
+    }
+
+    /// Writes the mesh as Wavefront OBJ with positions, texture coordinates and normals.
+    pub fn write_obj<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        for p in &self.positions {
+            writeln!(writer, "v {} {} {}", p.x, p.y, p.z)?;
+        }
+        for t in &self.uvs {
+            writeln!(writer, "vt {} {}", t.x, t.y)?;
+        }
+        for n in &self.normals {
+            writeln!(writer, "vn {} {} {}", n.x, n.y, n.z)?;
+        }
+        for triangle in &self.triangles {
+            // OBJ indices start at one
+            let [a, b, c] = triangle.map(|index| index + 1);
+            writeln!(writer, "f {a}/{a}/{a} {b}/{b}/{b} {c}/{c}/{c}")?;
+        }
+        Ok(())
+    }
+
+    /// Writes the mesh as binary STL.
+    ///
+    /// STL only stores one normal per face, so the geometric face normal is used.
+    pub fn write_stl<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(&[0; 80])?;
+        writer.write_all(&(self.triangles.len() as u32).to_le_bytes())?;
+        for triangle in &self.triangles {
+            let [a, b, c] = triangle.map(|index| self.positions[index as usize]);
+            let normal = (b - a).cross(&(c - a)).normalize();
+            for vector in [normal, a, b, c] {
+                for component in vector.iter() {
+                    writer.write_all(&component.to_le_bytes())?;
+                }
+            }
+            writer.write_all(&0_u16.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Writes the mesh as binary little-endian PLY with positions, normals and texture coordinates.
+    pub fn write_ply<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        write!(
+            writer,
+            "ply\n\
+             format binary_little_endian 1.0\n\
+             element vertex {}\n\
+             property float x\n\
+             property float y\n\
+             property float z\n\
+             property float nx\n\
+             property float ny\n\
+             property float nz\n\
+             property float s\n\
+             property float t\n\
+             element face {}\n\
+             property list uchar uint vertex_indices\n\
+             end_header\n",
+            self.positions.len(),
+            self.triangles.len()
+        )?;
+
+        for ((p, n), t) in self.positions.iter().zip(&self.normals).zip(&self.uvs) {
+            for component in p.iter().chain(n.iter()).chain(t.iter()) {
+                writer.write_all(&component.to_le_bytes())?;
+            }
+        }
+        for triangle in &self.triangles {
+            writer.write_all(&[3])?;
+            for index in triangle {
+                writer.write_all(&index.to_le_bytes())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Perlin;
+
+    fn mesh() -> Mesh {
+        Mesh::from_noise(&Perlin::from_seed((4, 4), 1), 0.2, (2, 2), false)
+    }
+
+    #[test]
+    fn grid_has_two_triangles_facing_up() {
+        let mesh = mesh();
+        assert_eq!(mesh.positions.len(), 4);
+        assert_eq!(mesh.normals.len(), 4);
+        assert_eq!(mesh.uvs.len(), 4);
+        assert_eq!(mesh.triangles, [[0, 1, 3], [0, 3, 2]]);
+    }
+
+    #[test]
+    fn obj_lists_every_element() {
+        let mut bytes = Vec::new();
+        mesh().write_obj(&mut bytes).unwrap();
+        let text = String::from_utf8(bytes).unwrap();
+        let count = |prefix: &str| text.lines().filter(|l| l.starts_with(prefix)).count();
+        assert_eq!(
+            (count("v "), count("vt "), count("vn "), count("f ")),
+            (4, 4, 4, 2)
+        );
+        assert!(text.contains("\nf 1/1/1 2/2/2 4/4/4\n"));
+    }
+
+    #[test]
+    fn stl_records_the_triangle_count() {
+        let mut bytes = Vec::new();
+        mesh().write_stl(&mut bytes).unwrap();
+        // An 80 byte header and the count, then 50 bytes per triangle
+        assert_eq!(bytes.len(), 84 + 2 * 50);
+        assert_eq!(bytes[80..84], 2_u32.to_le_bytes());
+        let normal_z = f32::from_le_bytes(bytes[92..96].try_into().unwrap());
+        assert!(normal_z > 0.0);
+    }
+
+    #[test]
+    fn ply_header_matches_the_body() {
+        let mut bytes = Vec::new();
+        mesh().write_ply(&mut bytes).unwrap();
+        let end = b"end_header\n";
+        let header_len = bytes.windows(end.len()).position(|w| w == end).unwrap() + end.len();
+        let header = std::str::from_utf8(&bytes[..header_len]).unwrap();
+        assert!(header.contains("element vertex 4\n"));
+        assert!(header.contains("element face 2\n"));
+        // Eight floats per vertex, and a count byte with three indices per face
+        assert_eq!(bytes.len(), header_len + 4 * 32 + 2 * 13);
+    }
+}
@@ -0,0 +1,418 @@
+use nalgebra::{Matrix2, Vector2};
+
+use crate::{
+    float::Float,
+    noise::{Noise, Noise64, NoiseAt},
+};
+
+/// Linearly remaps a noise function, `value * scale + bias`.
+pub struct ScaleBias<N = Box<dyn Noise>> {
+    noise: N,
+    scale: f32,
+    bias: f32,
+}
+
+impl<N> ScaleBias<N> {
+    pub fn new(noise: N, scale: f32, bias: f32) -> Self {
+        Self { noise, scale, bias }
+    }
+
+    fn sample_generic<F: Float>(&self, x: F, y: F) -> F
+    where
+        N: NoiseAt<F>,
+    {
+        self.noise.sample_at(x, y) * F::cast(self.scale as f64) + F::cast(self.bias as f64)
+    }
+
+    fn gradient_generic<F: Float>(&self, x: F, y: F) -> Vector2<F>
+    where
+        N: NoiseAt<F>,
+    {
+        self.noise.gradient_at(x, y) * F::cast(self.scale as f64)
+    }
+}
+
+impl<N: Noise> Noise for ScaleBias<N> {
+    fn sample(&self, x: f32, y: f32) -> f32 {
+        self.sample_generic(x, y)
+    }
+
+    fn gradient(&self, x: f32, y: f32) -> Vector2<f32> {
+        self.gradient_generic(x, y)
+    }
+
+    fn hessian(&self, x: f32, y: f32) -> Matrix2<f32> {
+        self.noise.hessian(x, y) * self.scale
+    }
+}
+
+impl<N: Noise64> Noise64 for ScaleBias<N> {
+    fn sample_f64(&self, x: f64, y: f64) -> f64 {
+        self.sample_generic(x, y)
+    }
+
+    fn gradient_f64(&self, x: f64, y: f64) -> Vector2<f64> {
+        self.gradient_generic(x, y)
+    }
+}
+
+/// Restricts a noise function to the range [lower, upper].
+///
+/// With a non-zero smoothness the corners are rounded off over that distance,
+/// so the gradient falls to zero gradually instead of jumping.
+pub struct Clamp<N = Box<dyn Noise>> {
+    noise: N,
+    lower: f32,
+    upper: f32,
+    smoothness: f32,
+}
+
+impl<N> Clamp<N> {
+    pub fn new(noise: N, lower: f32, upper: f32) -> Self {
+        Self::smooth(noise, lower, upper, 0.0)
+    }
+
+    pub fn smooth(noise: N, lower: f32, upper: f32, smoothness: f32) -> Self {
+        debug_assert!(lower <= upper);
+        debug_assert!(smoothness >= 0.0);
+
+        Self {
+            noise,
+            lower,
+            upper,
+            smoothness,
+        }
+    }
+
+    // Clamped value and its derivative with respect to the input value.
+    fn clamp<F: Float>(&self, value: F) -> (F, F) {
+        let lower = F::cast(self.lower as f64);
+        let upper = F::cast(self.upper as f64);
+        if self.smoothness <= 0.0 {
+            return if value < lower {
+                (lower, F::zero())
+            } else if value > upper {
+                (upper, F::zero())
+            } else {
+                (value, F::one())
+            };
+        }
+
+        let smoothness = F::cast(self.smoothness as f64);
+        let (value, d_upper) = smooth_min(value, upper, smoothness);
+        let (value, d_lower) = smooth_max(value, lower, smoothness);
+        (value, d_upper * d_lower)
+    }
+
+    fn sample_generic<F: Float>(&self, x: F, y: F) -> F
+    where
+        N: NoiseAt<F>,
+    {
+        self.clamp(self.noise.sample_at(x, y)).0
+    }
+
+    fn gradient_generic<F: Float>(&self, x: F, y: F) -> Vector2<F>
+    where
+        N: NoiseAt<F>,
+    {
+        let (_, derivative) = self.clamp(self.noise.sample_at(x, y));
+        self.noise.gradient_at(x, y) * derivative
+    }
+}
+
+impl<N: Noise> Noise for Clamp<N> {
+    fn sample(&self, x: f32, y: f32) -> f32 {
+        self.sample_generic(x, y)
+    }
+
+    fn gradient(&self, x: f32, y: f32) -> Vector2<f32> {
+        self.gradient_generic(x, y)
+    }
+}
+
+impl<N: Noise64> Noise64 for Clamp<N> {
+    fn sample_f64(&self, x: f64, y: f64) -> f64 {
+        self.sample_generic(x, y)
+    }
+
+    fn gradient_f64(&self, x: f64, y: f64) -> Vector2<f64> {
+        self.gradient_generic(x, y)
+    }
+}
+
+/// Absolute value of a noise function.
+pub struct Abs<N = Box<dyn Noise>> {
+    noise: N,
+}
+
+impl<N> Abs<N> {
+    pub fn new(noise: N) -> Self {
+        Self { noise }
+    }
+
+    fn sample_generic<F: Float>(&self, x: F, y: F) -> F
+    where
+        N: NoiseAt<F>,
+    {
+        self.noise.sample_at(x, y).abs()
+    }
+
+    fn gradient_generic<F: Float>(&self, x: F, y: F) -> Vector2<F>
+    where
+        N: NoiseAt<F>,
+    {
+        if self.noise.sample_at(x, y) < F::zero() {
+            -self.noise.gradient_at(x, y)
+        } else {
+            self.noise.gradient_at(x, y)
+        }
+    }
+}
+
+impl<N: Noise> Noise for Abs<N> {
+    fn sample(&self, x: f32, y: f32) -> f32 {
+        self.sample_generic(x, y)
+    }
+
+    fn gradient(&self, x: f32, y: f32) -> Vector2<f32> {
+        self.gradient_generic(x, y)
+    }
+}
+
+impl<N: Noise64> Noise64 for Abs<N> {
+    fn sample_f64(&self, x: f64, y: f64) -> f64 {
+        self.sample_generic(x, y)
+    }
+
+    fn gradient_f64(&self, x: f64, y: f64) -> Vector2<f64> {
+        self.gradient_generic(x, y)
+    }
+}
+
+/// Raises a noise function to a power, preserving its sign: sign(v) * |v|^exponent.
+pub struct Pow<N = Box<dyn Noise>> {
+    noise: N,
+    exponent: f32,
+}
+
+impl<N> Pow<N> {
+    pub fn new(noise: N, exponent: f32) -> Self {
+        Self { noise, exponent }
+    }
+
+    fn sample_generic<F: Float>(&self, x: F, y: F) -> F
+    where
+        N: NoiseAt<F>,
+    {
+        let value = self.noise.sample_at(x, y);
+        value.signum() * value.abs().powf(F::cast(self.exponent as f64))
+    }
+
+    fn gradient_generic<F: Float>(&self, x: F, y: F) -> Vector2<F>
+    where
+        N: NoiseAt<F>,
+    {
+        let value = self.noise.sample_at(x, y);
+        if value == F::zero() {
+            return Vector2::zeros();
+        }
+        let exponent = F::cast(self.exponent as f64);
+        self.noise.gradient_at(x, y) * (exponent * value.abs().powf(exponent - F::one()))
+    }
+}
+
+impl<N: Noise> Noise for Pow<N> {
+    fn sample(&self, x: f32, y: f32) -> f32 {
+        self.sample_generic(x, y)
+    }
+
+    fn gradient(&self, x: f32, y: f32) -> Vector2<f32> {
+        self.gradient_generic(x, y)
+    }
+}
+
+impl<N: Noise64> Noise64 for Pow<N> {
+    fn sample_f64(&self, x: f64, y: f64) -> f64 {
+        self.sample_generic(x, y)
+    }
+
+    fn gradient_f64(&self, x: f64, y: f64) -> Vector2<f64> {
+        self.gradient_generic(x, y)
+    }
+}
+
+/// Negates a noise function.
+pub struct Invert<N = Box<dyn Noise>> {
+    noise: N,
+}
+
+impl<N> Invert<N> {
+    pub fn new(noise: N) -> Self {
+        Self { noise }
+    }
+
+    fn sample_generic<F: Float>(&self, x: F, y: F) -> F
+    where
+        N: NoiseAt<F>,
+    {
+        -self.noise.sample_at(x, y)
+    }
+
+    fn gradient_generic<F: Float>(&self, x: F, y: F) -> Vector2<F>
+    where
+        N: NoiseAt<F>,
+    {
+        -self.noise.gradient_at(x, y)
+    }
+}
+
+impl<N: Noise> Noise for Invert<N> {
+    fn sample(&self, x: f32, y: f32) -> f32 {
+        self.sample_generic(x, y)
+    }
+
+    fn gradient(&self, x: f32, y: f32) -> Vector2<f32> {
+        self.gradient_generic(x, y)
+    }
+
+    fn hessian(&self, x: f32, y: f32) -> Matrix2<f32> {
+        -self.noise.hessian(x, y)
+    }
+}
+
+impl<N: Noise64> Noise64 for Invert<N> {
+    fn sample_f64(&self, x: f64, y: f64) -> f64 {
+        self.sample_generic(x, y)
+    }
+
+    fn gradient_f64(&self, x: f64, y: f64) -> Vector2<f64> {
+        self.gradient_generic(x, y)
+    }
+}
+
+/// Maps a noise function with values in [-1, 1] onto an exponential curve,
+/// ((v + 1) / 2)^exponent * 2 - 1, keeping the result within [-1, 1].
+pub struct Exponent<N = Box<dyn Noise>> {
+    noise: N,
+    exponent: f32,
+}
+
+impl<N> Exponent<N> {
+    pub fn new(noise: N, exponent: f32) -> Self {
+        Self { noise, exponent }
+    }
+
+    fn sample_generic<F: Float>(&self, x: F, y: F) -> F
+    where
+        N: NoiseAt<F>,
+    {
+        let t = (self.noise.sample_at(x, y) + F::one()) * F::cast(0.5);
+        t.abs().powf(F::cast(self.exponent as f64)) * F::cast(2.0) - F::one()
+    }
+
+    fn gradient_generic<F: Float>(&self, x: F, y: F) -> Vector2<F>
+    where
+        N: NoiseAt<F>,
+    {
+        let t = (self.noise.sample_at(x, y) + F::one()) * F::cast(0.5);
+        if t == F::zero() {
+            return Vector2::zeros();
+        }
+        let exponent = F::cast(self.exponent as f64);
+        self.noise.gradient_at(x, y) * (exponent * t.abs().powf(exponent - F::one()) * t.signum())
+    }
+}
+
+impl<N: Noise> Noise for Exponent<N> {
+    fn sample(&self, x: f32, y: f32) -> f32 {
+        self.sample_generic(x, y)
+    }
+
+    fn gradient(&self, x: f32, y: f32) -> Vector2<f32> {
+        self.gradient_generic(x, y)
+    }
+}
+
+impl<N: Noise64> Noise64 for Exponent<N> {
+    fn sample_f64(&self, x: f64, y: f64) -> f64 {
+        self.sample_generic(x, y)
+    }
+
+    fn gradient_f64(&self, x: f64, y: f64) -> Vector2<f64> {
+        self.gradient_generic(x, y)
+    }
+}
+
+// Polynomial smooth minimum of `a` and `b` over width `k`, and its derivative with respect to `a`.
+fn smooth_min<F: Float>(a: F, b: F, k: F) -> (F, F) {
+    let c = F::cast;
+    let d = a - b;
+    if d <= -k {
+        (a, F::one())
+    } else if d >= k {
+        (b, F::zero())
+    } else {
+        (
+            c(0.5) * (a + b) - c(0.25) * k - d * d / (c(4.0) * k),
+            c(0.5) - d / (c(2.0) * k),
+        )
+    }
+}
+
+// Polynomial smooth maximum of `a` and `b` over width `k`, and its derivative with respect to `a`.
+fn smooth_max<F: Float>(a: F, b: F, k: F) -> (F, F) {
+    let c = F::cast;
+    let d = a - b;
+    if d >= k {
+        (a, F::one())
+    } else if d <= -k {
+        (b, F::zero())
+    } else {
+        (
+            c(0.5) * (a + b) + c(0.25) * k + d * d / (c(4.0) * k),
+            c(0.5) + d / (c(2.0) * k),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        noise::{assert_f64_matches, assert_gradient_matches, assert_hessian_matches},
+        Perlin,
+    };
+
+    fn perlin() -> Box<dyn Noise> {
+        Box::new(Perlin::from_seed((4, 4), 1))
+    }
+
+    #[test]
+    fn gradients_match_finite_differences() {
+        assert_gradient_matches(&ScaleBias::new(perlin(), 2.0, 0.5));
+        assert_gradient_matches(&Clamp::new(perlin(), -0.2, 0.3));
+        assert_gradient_matches(&Clamp::smooth(perlin(), -0.2, 0.3, 0.1));
+        assert_gradient_matches(&Abs::new(perlin()));
+        assert_gradient_matches(&Pow::new(perlin(), 2.5));
+        assert_gradient_matches(&Invert::new(perlin()));
+        assert_gradient_matches(&Exponent::new(perlin(), 2.0));
+    }
+
+    #[test]
+    fn hessians_match_finite_differences() {
+        assert_hessian_matches(&ScaleBias::new(perlin(), 2.0, 0.5));
+        assert_hessian_matches(&Invert::new(perlin()));
+    }
+
+    #[test]
+    fn f64_matches_f32() {
+        let perlin = || Perlin::from_seed((4, 4), 1);
+        assert_f64_matches(&ScaleBias::new(perlin(), 2.0, 0.5));
+        assert_f64_matches(&Clamp::new(perlin(), -0.2, 0.3));
+        assert_f64_matches(&Clamp::smooth(perlin(), -0.2, 0.3, 0.1));
+        assert_f64_matches(&Abs::new(perlin()));
+        assert_f64_matches(&Pow::new(perlin(), 2.5));
+        assert_f64_matches(&Invert::new(perlin()));
+        assert_f64_matches(&Exponent::new(perlin(), 2.0));
+    }
+}
@@ -1,6 +1,257 @@
-use nalgebra::Vector2;
+use nalgebra::{Matrix2, Vector2, Vector3, Vector4};
+
+use crate::float::Float;
 
 pub trait Noise {
     fn sample(&self, u: f32, v: f32) -> f32;
     fn gradient(&self, u: f32, v: f32) -> Vector2<f32>;
+
+    /// Matrix of second derivatives.
+    ///
+    /// By default this is approximated with central differences of the gradient over a step of `1e-3`,
+    /// so noises with features near that size should override it.
+    fn hessian(&self, u: f32, v: f32) -> Matrix2<f32> {
+        let epsilon = 1e-3;
+        let du = (self.gradient(u + epsilon, v) - self.gradient(u - epsilon, v)) / (2.0 * epsilon);
+        let dv = (self.gradient(u, v + epsilon) - self.gradient(u, v - epsilon)) / (2.0 * epsilon);
+        let cross = 0.5 * (du.y + dv.x);
+        Matrix2::new(du.x, cross, cross, dv.y)
+    }
+}
+
+impl<N: Noise + ?Sized> Noise for Box<N> {
+    fn sample(&self, u: f32, v: f32) -> f32 {
+        (**self).sample(u, v)
+    }
+
+    fn gradient(&self, u: f32, v: f32) -> Vector2<f32> {
+        (**self).gradient(u, v)
+    }
+
+    fn hessian(&self, u: f32, v: f32) -> Matrix2<f32> {
+        (**self).hessian(u, v)
+    }
+}
+
+/// Double precision counterpart of [`Noise`], for sampling far from the origin or deep into a large world.
+///
+/// The methods are named apart from those of [`Noise`], so calls on a generator implementing both are never ambiguous.
+pub trait Noise64 {
+    fn sample_f64(&self, u: f64, v: f64) -> f64;
+    fn gradient_f64(&self, u: f64, v: f64) -> Vector2<f64>;
+}
+
+impl<N: Noise64 + ?Sized> Noise64 for Box<N> {
+    fn sample_f64(&self, u: f64, v: f64) -> f64 {
+        (**self).sample_f64(u, v)
+    }
+
+    fn gradient_f64(&self, u: f64, v: f64) -> Vector2<f64> {
+        (**self).gradient_f64(u, v)
+    }
+}
+
+// Either `Noise` or `Noise64`, chosen by precision, so that a wrapper can implement both with one generic body.
+pub(crate) trait NoiseAt<F: Float> {
+    fn sample_at(&self, u: F, v: F) -> F;
+    fn gradient_at(&self, u: F, v: F) -> Vector2<F>;
+}
+
+impl<N: Noise + ?Sized> NoiseAt<f32> for N {
+    fn sample_at(&self, u: f32, v: f32) -> f32 {
+        self.sample(u, v)
+    }
+
+    fn gradient_at(&self, u: f32, v: f32) -> Vector2<f32> {
+        self.gradient(u, v)
+    }
+}
+
+impl<N: Noise64 + ?Sized> NoiseAt<f64> for N {
+    fn sample_at(&self, u: f64, v: f64) -> f64 {
+        self.sample_f64(u, v)
+    }
+
+    fn gradient_at(&self, u: f64, v: f64) -> Vector2<f64> {
+        self.gradient_f64(u, v)
+    }
+}
+
+/// Volumetric noise, sampled at a point in 3D space.
+///
+/// Tileable implementations repeat over the unit cube, in the same way as [`Noise`] repeats over the unit square.
+pub trait Noise3 {
+    fn sample(&self, x: f32, y: f32, z: f32) -> f32;
+    fn gradient(&self, x: f32, y: f32, z: f32) -> Vector3<f32>;
+}
+
+impl<N: Noise3 + ?Sized> Noise3 for Box<N> {
+    fn sample(&self, x: f32, y: f32, z: f32) -> f32 {
+        (**self).sample(x, y, z)
+    }
+
+    fn gradient(&self, x: f32, y: f32, z: f32) -> Vector3<f32> {
+        (**self).gradient(x, y, z)
+    }
+}
+
+/// Double precision counterpart of [`Noise3`], named apart from it in the same way as [`Noise64`].
+pub trait Noise3_64 {
+    fn sample_f64(&self, x: f64, y: f64, z: f64) -> f64;
+    fn gradient_f64(&self, x: f64, y: f64, z: f64) -> Vector3<f64>;
+}
+
+impl<N: Noise3_64 + ?Sized> Noise3_64 for Box<N> {
+    fn sample_f64(&self, x: f64, y: f64, z: f64) -> f64 {
+        (**self).sample_f64(x, y, z)
+    }
+
+    fn gradient_f64(&self, x: f64, y: f64, z: f64) -> Vector3<f64> {
+        (**self).gradient_f64(x, y, z)
+    }
+}
+
+/// Noise over four dimensions, such as 3D space plus time.
+pub trait Noise4 {
+    fn sample(&self, x: f32, y: f32, z: f32, w: f32) -> f32;
+    fn gradient(&self, x: f32, y: f32, z: f32, w: f32) -> Vector4<f32>;
+}
+
+impl<N: Noise4 + ?Sized> Noise4 for Box<N> {
+    fn sample(&self, x: f32, y: f32, z: f32, w: f32) -> f32 {
+        (**self).sample(x, y, z, w)
+    }
+
+    fn gradient(&self, x: f32, y: f32, z: f32, w: f32) -> Vector4<f32> {
+        (**self).gradient(x, y, z, w)
+    }
+}
+
+/// Double precision counterpart of [`Noise4`], named apart from it in the same way as [`Noise64`].
+pub trait Noise4_64 {
+    fn sample_f64(&self, x: f64, y: f64, z: f64, w: f64) -> f64;
+    fn gradient_f64(&self, x: f64, y: f64, z: f64, w: f64) -> Vector4<f64>;
+}
+
+impl<N: Noise4_64 + ?Sized> Noise4_64 for Box<N> {
+    fn sample_f64(&self, x: f64, y: f64, z: f64, w: f64) -> f64 {
+        (**self).sample_f64(x, y, z, w)
+    }
+
+    fn gradient_f64(&self, x: f64, y: f64, z: f64, w: f64) -> Vector4<f64> {
+        (**self).gradient_f64(x, y, z, w)
+    }
+}
+
+/// Noise along a single axis, for signals such as camera shake, flicker or audio modulation.
+pub trait Noise1 {
+    fn sample(&self, x: f32) -> f32;
+    fn derivative(&self, x: f32) -> f32;
+}
+
+impl<N: Noise1 + ?Sized> Noise1 for Box<N> {
+    fn sample(&self, x: f32) -> f32 {
+        (**self).sample(x)
+    }
+
+    fn derivative(&self, x: f32) -> f32 {
+        (**self).derivative(x)
+    }
+}
+
+/// Double precision counterpart of [`Noise1`], named apart from it in the same way as [`Noise64`].
+pub trait Noise1_64 {
+    fn sample_f64(&self, x: f64) -> f64;
+    fn derivative_f64(&self, x: f64) -> f64;
+}
+
+impl<N: Noise1_64 + ?Sized> Noise1_64 for Box<N> {
+    fn sample_f64(&self, x: f64) -> f64 {
+        (**self).sample_f64(x)
+    }
+
+    fn derivative_f64(&self, x: f64) -> f64 {
+        (**self).derivative_f64(x)
+    }
+}
+
+// Checks the gradient against central differences at points spread over the unit square.
+// Up to 1% of the points may mismatch, as they can straddle a crease such as the edge of a clamp.
+#[cfg(test)]
+pub(crate) fn assert_gradient_matches<N: Noise + ?Sized>(noise: &N) {
+    let h = 1e-4;
+    let points = 1000;
+    let mismatches = (0..points)
+        .filter(|&i| {
+            let (x, y) = (
+                (0.618_034 * i as f32) % 1.0,
+                (0.414_214 * i as f32 + 0.1) % 1.0,
+            );
+            let difference = Vector2::new(
+                (noise.sample(x + h, y) - noise.sample(x - h, y)) / (2.0 * h),
+                (noise.sample(x, y + h) - noise.sample(x, y - h)) / (2.0 * h),
+            );
+            let gradient = noise.gradient(x, y);
+            (difference - gradient).norm() > 0.05 * gradient.norm().max(1.0)
+        })
+        .count();
+    assert!(
+        mismatches <= points / 100,
+        "{} of {} points mismatch",
+        mismatches,
+        points
+    );
+}
+
+// Checks the Hessian against the symmetric part of central differences of the gradient,
+// in the same way as `assert_gradient_matches`.
+#[cfg(test)]
+pub(crate) fn assert_hessian_matches<N: Noise + ?Sized>(noise: &N) {
+    let h = 1e-4;
+    let points = 1000;
+    let mismatches = (0..points)
+        .filter(|&i| {
+            let (x, y) = (
+                (0.618_034 * i as f32) % 1.0,
+                (0.414_214 * i as f32 + 0.1) % 1.0,
+            );
+            let du = (noise.gradient(x + h, y) - noise.gradient(x - h, y)) / (2.0 * h);
+            let dv = (noise.gradient(x, y + h) - noise.gradient(x, y - h)) / (2.0 * h);
+            let jacobian = Matrix2::from_columns(&[du, dv]);
+            let difference = (jacobian + jacobian.transpose()) * 0.5;
+            let hessian = noise.hessian(x, y);
+            (difference - hessian).norm() > 0.05 * hessian.norm().max(1.0)
+        })
+        .count();
+    assert!(
+        mismatches <= points / 100,
+        "{} of {} points mismatch",
+        mismatches,
+        points
+    );
+}
+
+// Checks that sampling in `f64` agrees with `f32`, in the same way as `assert_gradient_matches`.
+#[cfg(test)]
+pub(crate) fn assert_f64_matches<N: Noise + Noise64 + ?Sized>(noise: &N) {
+    let points = 1000;
+    let mismatches = (0..points)
+        .filter(|&i| {
+            let (x, y) = (
+                (0.618_034 * i as f32) % 1.0,
+                (0.414_214 * i as f32 + 0.1) % 1.0,
+            );
+            let (sample, gradient) = (noise.sample(x, y), noise.gradient(x, y));
+            let sample_error = noise.sample_f64(x as f64, y as f64) - sample as f64;
+            let gradient_error = noise.gradient_f64(x as f64, y as f64) - gradient.cast::<f64>();
+            sample_error.abs() > 1e-3 * sample.abs().max(1.0) as f64
+                || gradient_error.norm() > 1e-2 * gradient.norm().max(1.0) as f64
+        })
+        .count();
+    assert!(
+        mismatches <= points / 100,
+        "{} of {} points mismatch",
+        mismatches,
+        points
+    );
 }
@@ -0,0 +1,101 @@
+use nalgebra::Vector3;
+use ndarray::{Array2, Array3};
+
+use crate::noise::Noise;
+
+/// Direction of the green (Y) channel of a tangent-space normal map.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NormalConvention {
+    /// Y points up the image, towards row 0 (used by OpenGL, Blender and Unity).
+    OpenGl,
+    /// Y points down the image, towards the last row (used by DirectX and Unreal).
+    DirectX,
+}
+
+/// Tangent-space normals of the surface `height_scale * noise(u, v)` over the unit square,
+/// computed directly from the analytic gradient.
+///
+/// The grid matches [`sample_grid`](crate::sample_grid), so row `i` is at `v = i / height`
+/// and column `j` at `u = j / width`.
+pub fn normal_map<N: Noise + ?Sized>(
+    noise: &N,
+    height_scale: f32,
+    resolution: (usize, usize),
+    convention: NormalConvention,
+) -> Array2<Vector3<f32>> {
+    let (height, width) = resolution;
+    Array2::from_shape_fn(resolution, |(i, j)| {
+        let u = j as f32 / width as f32;
+        let v = i as f32 / height as f32;
+        surface_normal(noise, height_scale, u, v, convention)
+    })
+}
+
+/// Encodes normals as 8-bit RGB, mapping each component from [-1, 1] to [0, 255].
+///
+/// The result has shape `(height, width, 3)`.
+pub fn encode_rgb8(normals: &Array2<Vector3<f32>>) -> Array3<u8> {
+    encode(normals, |c| (c * 255.0).round() as u8)
+}
+
+/// Encodes normals as 16-bit RGB, mapping each component from [-1, 1] to [0, 65535].
+///
+/// The result has shape `(height, width, 3)`.
+pub fn encode_rgb16(normals: &Array2<Vector3<f32>>) -> Array3<u16> {
+    encode(normals, |c| (c * 65535.0).round() as u16)
+}
+
+fn encode<T>(normals: &Array2<Vector3<f32>>, quantise: impl Fn(f32) -> T) -> Array3<T> {
+    let (height, width) = normals.dim();
+    Array3::from_shape_fn((height, width, 3), |(i, j, c)| {
+        quantise((normals[(i, j)][c] * 0.5 + 0.5).clamp(0.0, 1.0))
+    })
+}
+
+fn surface_normal<N: Noise + ?Sized>(
+    noise: &N,
+    height_scale: f32,
+    u: f32,
+    v: f32,
+    convention: NormalConvention,
+) -> Vector3<f32> {
+    let gradient = noise.gradient(u, v) * height_scale;
+    // The v axis runs down the image, so OpenGL's upward Y sees the opposite slope
+    let ny = match convention {
+        NormalConvention::OpenGl => gradient.y,
+        NormalConvention::DirectX => -gradient.y,
+    };
+    Vector3::new(-gradient.x, ny, 1.0).normalize()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{OpenSimplex, Perlin, Worley};
+
+    // Largest difference between normals on opposite edges of the unit square, checked at `samples` points per edge.
+    fn normal_seam_error<N: Noise + ?Sized>(noise: &N, height_scale: f32, samples: usize) -> f32 {
+        let normal = |u, v| surface_normal(noise, height_scale, u, v, NormalConvention::OpenGl);
+        (0..samples)
+            .map(|k| k as f32 / samples as f32)
+            .map(|t| {
+                let horizontal = (normal(0.0, t) - normal(1.0, t)).norm();
+                let vertical = (normal(t, 0.0) - normal(t, 1.0)).norm();
+                horizontal.max(vertical)
+            })
+            .fold(0.0, f32::max)
+    }
+
+    #[test]
+    fn tileable_noise_has_no_seam() {
+        let noises: [Box<dyn Noise>; 3] = [
+            Box::new(Perlin::from_seed((5, 7), 1)),
+            Box::new(OpenSimplex::from_seed(4.0, 2)),
+            Box::new(Worley::from_seed(20, 3)),
+        ];
+        for noise in &noises {
+            let error = normal_seam_error(noise.as_ref(), 0.2, 256);
+            assert!(error < 1e-3, "{}", error);
+        }
+    }
+}
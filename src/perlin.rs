@@ -1,12 +1,21 @@
 use std::f32::consts::TAU;
 
-use nalgebra::{Unit, Vector2};
+use nalgebra::{Matrix2, Unit, Vector2};
 use ndarray::Array2;
 use rand::Rng;
 
-use crate::noise::Noise;
+use crate::{
+    float::Float,
+    noise::{Noise, Noise64},
+    seed,
+};
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Perlin {
+    #[cfg_attr(
+        feature = "serde",
+        serde(deserialize_with = "crate::perlin::deserialize_lattice")
+    )]
     vectors: Array2<Unit<Vector2<f32>>>,
 }
 
@@ -15,46 +24,53 @@ impl Perlin {
         assert!(shape.0 > 0 && shape.1 > 0);
 
         let vectors = Array2::from_shape_fn(shape, |(_x, _y)| {
-            let angle = TAU * rng.random::<f32>();
+            let angle = TAU * seed::unit(&mut rng);
             Unit::new_normalize(Vector2::new(angle.cos(), angle.sin()))
         });
 
         Self { vectors }
     }
 
-    fn grad_dot(&self, gx: i32, gy: i32, x: f32, y: f32) -> f32 {
-        self.gradient(gx, gy).dot(&Vector2::new(x, y))
+    /// Creates a Perlin noise generator which is reproducible from the given seed.
+    pub fn from_seed(shape: (usize, usize), seed: u64) -> Self {
+        Self::new(shape, seed::rng(seed))
     }
 
-    fn gradient(&self, x: i32, y: i32) -> &Unit<Vector2<f32>> {
+    fn grad_dot<F: Float>(&self, gx: i64, gy: i64, x: F, y: F) -> F {
+        let vector = self.lattice(gx, gy);
+        F::cast(vector.x as f64) * x + F::cast(vector.y as f64) * y
+    }
+
+    fn lattice(&self, x: i64, y: i64) -> &Unit<Vector2<f32>> {
         let (rows, cols) = self.vectors.dim();
-        let nx = x.rem_euclid(cols as i32) as usize;
-        let ny = y.rem_euclid(rows as i32) as usize;
+        let nx = x.rem_euclid(cols as i64) as usize;
+        let ny = y.rem_euclid(rows as i64) as usize;
         &self.vectors[(ny, nx)]
     }
 }
 
-impl Noise for Perlin {
-    fn sample(&self, x: f32, y: f32) -> f32 {
+impl Perlin {
+    fn sample_generic<F: Float>(&self, x: F, y: F) -> F {
         let (width, height) = self.vectors.dim();
-        let px = x * width as f32;
-        let py = y * height as f32;
+        let px = x * F::cast(width as f64);
+        let py = y * F::cast(height as f64);
 
         // Grid cell coordinates
-        let x0 = (px.floor() as i32) % width as i32;
-        let y0 = (py.floor() as i32) % height as i32;
-        let x1 = (x0 + 1) % width as i32;
-        let y1 = (y0 + 1) % height as i32;
+        let x0 = (px.floor().to_f64() as i64) % width as i64;
+        let y0 = (py.floor().to_f64() as i64) % height as i64;
+        let x1 = (x0 + 1) % width as i64;
+        let y1 = (y0 + 1) % height as i64;
 
         // Local coordinates within the cell
         let xf = px - px.floor();
         let yf = py - py.floor();
+        let one = F::one();
 
         // Dot products with gradient vectors
         let g00 = self.grad_dot(x0, y0, xf, yf);
-        let g10 = self.grad_dot(x1, y0, xf - 1.0, yf);
-        let g01 = self.grad_dot(x0, y1, xf, yf - 1.0);
-        let g11 = self.grad_dot(x1, y1, xf - 1.0, yf - 1.0);
+        let g10 = self.grad_dot(x1, y0, xf - one, yf);
+        let g01 = self.grad_dot(x0, y1, xf, yf - one);
+        let g11 = self.grad_dot(x1, y1, xf - one, yf - one);
 
         // Interpolation
         let u = fade(xf);
@@ -65,23 +81,24 @@ impl Noise for Perlin {
         lerp(nx0, nx1, v)
     }
 
-    fn gradient(&self, x: f32, y: f32) -> Vector2<f32> {
+    fn gradient_generic<F: Float>(&self, x: F, y: F) -> Vector2<F> {
         let (width, height) = self.vectors.dim();
-        let px = x * width as f32;
-        let py = y * height as f32;
+        let px = x * F::cast(width as f64);
+        let py = y * F::cast(height as f64);
 
-        let x0 = (px.floor() as i32) % width as i32;
-        let y0 = (py.floor() as i32) % height as i32;
-        let x1 = (x0 + 1) % width as i32;
-        let y1 = (y0 + 1) % height as i32;
+        let x0 = (px.floor().to_f64() as i64) % width as i64;
+        let y0 = (py.floor().to_f64() as i64) % height as i64;
+        let x1 = (x0 + 1) % width as i64;
+        let y1 = (y0 + 1) % height as i64;
 
         let xf = px - px.floor();
         let yf = py - py.floor();
+        let one = F::one();
 
         let g00 = self.grad_dot(x0, y0, xf, yf);
-        let g10 = self.grad_dot(x1, y0, xf - 1.0, yf);
-        let g01 = self.grad_dot(x0, y1, xf, yf - 1.0);
-        let g11 = self.grad_dot(x1, y1, xf - 1.0, yf - 1.0);
+        let g10 = self.grad_dot(x1, y0, xf - one, yf);
+        let g01 = self.grad_dot(x0, y1, xf, yf - one);
+        let g11 = self.grad_dot(x1, y1, xf - one, yf - one);
 
         let u = fade(xf);
         let v = fade(yf);
@@ -89,21 +106,147 @@ impl Noise for Perlin {
         let du = fade_derivative(xf);
         let dv = fade_derivative(yf);
 
-        let d_nx = (1.0 - v) * (g10 - g00) * du + v * (g11 - g01) * du;
-        let d_ny = (1.0 - u) * (g01 - g00) * dv + u * (g11 - g10) * dv;
+        // Each corner contributes its own gradient vector as well as the change in interpolation weights
+        let corner = |gx, gy| self.lattice(gx, gy).map(|c| F::cast(c as f64));
+        let c00 = corner(x0, y0);
+        let c10 = corner(x1, y0);
+        let c01 = corner(x0, y1);
+        let c11 = corner(x1, y1);
+
+        let nx0 = lerp(g00, g10, u);
+        let nx1 = lerp(g01, g11, u);
 
-        Vector2::new(d_nx, d_ny)
+        let d_nx = (one - v) * (lerp(c00.x, c10.x, u) + du * (g10 - g00))
+            + v * (lerp(c01.x, c11.x, u) + du * (g11 - g01));
+        let d_ny = (one - v) * lerp(c00.y, c10.y, u) + v * lerp(c01.y, c11.y, u) + dv * (nx1 - nx0);
+
+        // Chain rule: the cell coordinates are scaled by the lattice size
+        Vector2::new(d_nx * F::cast(width as f64), d_ny * F::cast(height as f64))
     }
 }
 
-fn lerp(a: f32, b: f32, t: f32) -> f32 {
+impl Noise for Perlin {
+    fn sample(&self, x: f32, y: f32) -> f32 {
+        self.sample_generic(x, y)
+    }
+
+    fn gradient(&self, x: f32, y: f32) -> Vector2<f32> {
+        self.gradient_generic(x, y)
+    }
+
+    fn hessian(&self, x: f32, y: f32) -> Matrix2<f32> {
+        let (width, height) = self.vectors.dim();
+        let (width, height) = (width as f32, height as f32);
+        let px = x * width;
+        let py = y * height;
+
+        let x0 = (px.floor() as i64) % width as i64;
+        let y0 = (py.floor() as i64) % height as i64;
+        let x1 = (x0 + 1) % width as i64;
+        let y1 = (y0 + 1) % height as i64;
+
+        let xf = px - px.floor();
+        let yf = py - py.floor();
+
+        let g00 = self.grad_dot(x0, y0, xf, yf);
+        let g10 = self.grad_dot(x1, y0, xf - 1.0, yf);
+        let g01 = self.grad_dot(x0, y1, xf, yf - 1.0);
+        let g11 = self.grad_dot(x1, y1, xf - 1.0, yf - 1.0);
+
+        let (u, v) = (fade(xf), fade(yf));
+        let (du, dv) = (fade_derivative(xf), fade_derivative(yf));
+        let (ddu, ddv) = (fade_second_derivative(xf), fade_second_derivative(yf));
+
+        let c00 = self.lattice(x0, y0);
+        let c10 = self.lattice(x1, y0);
+        let c01 = self.lattice(x0, y1);
+        let c11 = self.lattice(x1, y1);
+
+        // Rows of the cell, a along y0 and b along y1, interpolated in x.
+        // Each dot product is linear in the offset, so only the fade curves have second derivatives.
+        let (a, b) = (lerp(g00, g10, u), lerp(g01, g11, u));
+        let a_x = lerp(c00.x, c10.x, u) + du * (g10 - g00);
+        let b_x = lerp(c01.x, c11.x, u) + du * (g11 - g01);
+        let a_y = lerp(c00.y, c10.y, u);
+        let b_y = lerp(c01.y, c11.y, u);
+        let a_xx = 2.0 * du * (c10.x - c00.x) + ddu * (g10 - g00);
+        let b_xx = 2.0 * du * (c11.x - c01.x) + ddu * (g11 - g01);
+        let a_xy = du * (c10.y - c00.y);
+        let b_xy = du * (c11.y - c01.y);
+
+        let d_xx = lerp(a_xx, b_xx, v);
+        let d_xy = lerp(a_xy, b_xy, v) + dv * (b_x - a_x);
+        let d_yy = 2.0 * dv * (b_y - a_y) + ddv * (b - a);
+
+        // Chain rule, as for the gradient
+        Matrix2::new(
+            d_xx * width * width,
+            d_xy * width * height,
+            d_xy * width * height,
+            d_yy * height * height,
+        )
+    }
+}
+
+impl Noise64 for Perlin {
+    fn sample_f64(&self, x: f64, y: f64) -> f64 {
+        self.sample_generic(x, y)
+    }
+
+    fn gradient_f64(&self, x: f64, y: f64) -> Vector2<f64> {
+        self.gradient_generic(x, y)
+    }
+}
+
+pub(crate) fn lerp<F: Float>(a: F, b: F, t: F) -> F {
     a + t * (b - a)
 }
 
-fn fade(t: f32) -> f32 {
-    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+pub(crate) fn fade<F: Float>(t: F) -> F {
+    let c = F::cast;
+    t * t * t * (t * (t * c(6.0) - c(15.0)) + c(10.0))
+}
+
+pub(crate) fn fade_derivative<F: Float>(t: F) -> F {
+    let c = F::cast;
+    c(30.0) * t * t - c(60.0) * t * t * t + c(30.0) * t * t * t * t
+}
+
+pub(crate) fn fade_second_derivative<F: Float>(t: F) -> F {
+    let c = F::cast;
+    c(60.0) * t - c(180.0) * t * t + c(120.0) * t * t * t
+}
+
+// Rejects a lattice with no cells along some axis, which would leave no gradient to wrap coordinates onto.
+#[cfg(feature = "serde")]
+pub(crate) fn deserialize_lattice<'de, D, A, S>(
+    deserializer: D,
+) -> Result<ndarray::Array<A, S>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    A: serde::Deserialize<'de>,
+    S: ndarray::Dimension + serde::Deserialize<'de>,
+{
+    use serde::{de::Error, Deserialize};
+
+    let vectors = ndarray::Array::<A, S>::deserialize(deserializer)?;
+    if vectors.is_empty() {
+        return Err(D::Error::custom(format!(
+            "lattice of shape {:?} has no cells",
+            vectors.shape()
+        )));
+    }
+    Ok(vectors)
 }
 
-fn fade_derivative(t: f32) -> f32 {
-    30.0 * t * t - 60.0 * t * t * t + 30.0 * t * t * t * t
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::noise::assert_hessian_matches;
+
+    #[test]
+    fn hessian_matches_finite_differences() {
+        assert_hessian_matches(&Perlin::from_seed((4, 4), 1));
+        assert_hessian_matches(&Perlin::from_seed((3, 5), 2));
+    }
 }
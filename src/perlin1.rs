@@ -0,0 +1,80 @@
+use rand::Rng;
+
+use crate::{
+    float::Float,
+    lattice1::Lattice1,
+    noise::{Noise1, Noise1_64},
+    perlin::{fade, fade_derivative, lerp},
+    seed,
+};
+
+/// Perlin noise in one dimension, interpolating random slopes at integer lattice points.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct Perlin1 {
+    lattice: Lattice1,
+}
+
+impl Perlin1 {
+    pub fn new<R: Rng>(scale: f32, rng: R) -> Self {
+        Self {
+            lattice: Lattice1::new(scale, rng),
+        }
+    }
+
+    /// Creates a 1D Perlin noise generator which is reproducible from the given seed.
+    pub fn from_seed(scale: f32, seed: u64) -> Self {
+        Self::new(scale, seed::rng(seed))
+    }
+
+    /// Makes the noise repeat over the unit interval, rounding the scale to a whole number of lattice cells.
+    pub fn periodic(self) -> Self {
+        Self {
+            lattice: self.lattice.periodic(),
+        }
+    }
+}
+
+impl Perlin1 {
+    fn sample_generic<F: Float>(&self, x: F) -> F {
+        let (i, xf) = self.lattice.locate(x);
+
+        let a = self.lattice.value::<F>(i) * xf;
+        let b = self.lattice.value::<F>(i + 1) * (xf - F::one());
+
+        // Scale the result into roughly [-1, 1]
+        F::cast(2.0) * lerp(a, b, fade(xf))
+    }
+
+    fn derivative_generic<F: Float>(&self, x: F) -> F {
+        let (i, xf) = self.lattice.locate(x);
+
+        let (g0, g1) = (self.lattice.value::<F>(i), self.lattice.value::<F>(i + 1));
+        let a = g0 * xf;
+        let b = g1 * (xf - F::one());
+
+        let d = lerp(g0, g1, fade(xf)) + fade_derivative(xf) * (b - a);
+        // Chain rule: f(x) = g(scale*x), so df/dx = scale * g'(scale*x)
+        F::cast(2.0) * d * self.lattice.scale()
+    }
+}
+
+impl Noise1 for Perlin1 {
+    fn sample(&self, x: f32) -> f32 {
+        self.sample_generic(x)
+    }
+
+    fn derivative(&self, x: f32) -> f32 {
+        self.derivative_generic(x)
+    }
+}
+
+impl Noise1_64 for Perlin1 {
+    fn sample_f64(&self, x: f64) -> f64 {
+        self.sample_generic(x)
+    }
+
+    fn derivative_f64(&self, x: f64) -> f64 {
+        self.derivative_generic(x)
+    }
+}
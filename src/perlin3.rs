@@ -0,0 +1,125 @@
+use std::f32::consts::TAU;
+
+use nalgebra::{Unit, Vector3};
+use ndarray::Array3;
+use rand::Rng;
+
+use crate::{
+    float::Float,
+    noise::{Noise3, Noise3_64},
+    perlin::{fade, fade_derivative},
+    seed,
+};
+
+/// Perlin noise in three dimensions, tileable over the unit cube.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Perlin3 {
+    #[cfg_attr(
+        feature = "serde",
+        serde(deserialize_with = "crate::perlin::deserialize_lattice")
+    )]
+    vectors: Array3<Unit<Vector3<f32>>>,
+}
+
+impl Perlin3 {
+    /// Creates a lattice of `(depth, height, width)` cells along z, y and x,
+    /// with a random unit gradient vector at each lattice point.
+    pub fn new<R: Rng>(shape: (usize, usize, usize), mut rng: R) -> Self {
+        assert!(shape.0 > 0 && shape.1 > 0 && shape.2 > 0);
+
+        // Uniform on the sphere: uniform height along the z axis and uniform angle around it
+        let vectors = Array3::from_shape_fn(shape, |_| {
+            let z = 2.0 * seed::unit(&mut rng) - 1.0;
+            let angle = TAU * seed::unit(&mut rng);
+            let r = (1.0 - z * z).sqrt();
+            Unit::new_normalize(Vector3::new(r * angle.cos(), r * angle.sin(), z))
+        });
+
+        Self { vectors }
+    }
+
+    /// Creates a 3D Perlin noise generator which is reproducible from the given seed.
+    pub fn from_seed(shape: (usize, usize, usize), seed: u64) -> Self {
+        Self::new(shape, seed::rng(seed))
+    }
+
+    fn lattice(&self, x: i32, y: i32, z: i32) -> &Unit<Vector3<f32>> {
+        let (depth, height, width) = self.vectors.dim();
+        let nx = x.rem_euclid(width as i32) as usize;
+        let ny = y.rem_euclid(height as i32) as usize;
+        let nz = z.rem_euclid(depth as i32) as usize;
+        &self.vectors[(nz, ny, nx)]
+    }
+
+    // Value and gradient together, as each corner contributes to both.
+    fn evaluate<F: Float>(&self, x: F, y: F, z: F) -> (F, Vector3<F>) {
+        let (depth, height, width) = self.vectors.dim();
+        let scale = Vector3::new(width, height, depth).map(|cells| F::cast(cells as f64));
+        let p = Vector3::new(x, y, z).component_mul(&scale);
+        let cell = p.map(|c| c.floor());
+        let local = p - cell;
+        let (ix, iy, iz) = (
+            cell.x.to_f64() as i32,
+            cell.y.to_f64() as i32,
+            cell.z.to_f64() as i32,
+        );
+
+        let weight = local.map(fade);
+        let weight_derivative = local.map(fade_derivative);
+
+        let mut value = F::zero();
+        let mut gradient = Vector3::zeros();
+        for corner in 0..8 {
+            let corner = Vector3::new(corner & 1, (corner >> 1) & 1, (corner >> 2) & 1);
+            let offset = corner.map(|c| F::cast(c as f64));
+            let vector = self
+                .lattice(ix + corner.x, iy + corner.y, iz + corner.z)
+                .map(|c| F::cast(c as f64));
+            let dot = vector.dot(&(local - offset));
+
+            // Trilinear weights use t on the far side of the cell and 1 - t on the near side
+            let w = Vector3::from_fn(|k, _| {
+                if corner[k] == 0 {
+                    F::one() - weight[k]
+                } else {
+                    weight[k]
+                }
+            });
+            let dw = Vector3::from_fn(|k, _| {
+                if corner[k] == 0 {
+                    -weight_derivative[k]
+                } else {
+                    weight_derivative[k]
+                }
+            });
+
+            let product = w.x * w.y * w.z;
+            value += product * dot;
+            gradient += vector * product
+                + Vector3::new(dw.x * w.y * w.z, w.x * dw.y * w.z, w.x * w.y * dw.z) * dot;
+        }
+
+        // Chain rule: the cell coordinates are scaled by the lattice size
+        (value, gradient.component_mul(&scale))
+    }
+}
+
+impl Noise3 for Perlin3 {
+    fn sample(&self, x: f32, y: f32, z: f32) -> f32 {
+        self.evaluate(x, y, z).0
+    }
+
+    fn gradient(&self, x: f32, y: f32, z: f32) -> Vector3<f32> {
+        self.evaluate(x, y, z).1
+    }
+}
+
+impl Noise3_64 for Perlin3 {
+    fn sample_f64(&self, x: f64, y: f64, z: f64) -> f64 {
+        self.evaluate(x, y, z).0
+    }
+
+    fn gradient_f64(&self, x: f64, y: f64, z: f64) -> Vector3<f64> {
+        self.evaluate(x, y, z).1
+    }
+}
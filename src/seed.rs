@@ -0,0 +1,176 @@
+//! Deterministic seeding.
+//!
+//! Every `from_seed` constructor draws from a `ChaCha8Rng` seeded with `ChaCha8Rng::seed_from_u64(seed)`,
+//! whose output stream is fixed across versions and platforms.
+//! Generators only consume whole `u32` words from the stream, converting them with the functions below
+//! rather than any `rand` distribution, so a given seed always draws the same values:
+//!
+//! - `Perlin`: one angle per lattice point in row-major order, `TAU * unit(word)`.
+//! - `Simplex` / `OpenSimplex`: a Fisher-Yates shuffle of `0..256`, swapping index `i` (from 255 down to 1)
+//!   with `index(i + 1)`.
+//! - `Worley`: `unit(word)` for the x and then the y coordinate of each feature point.
+//! - `Perlin3`: two words per lattice point in `(z, y, x)` order, the height `2 * unit(word) - 1` along z
+//!   and then the angle `TAU * unit(word)` around it.
+//! - `Simplex3`, `Perlin1`, `Value1`, `Simplex1` and `LoopingSimplex`: the same shuffle as `Simplex`.
+//! - `Worley3`: `unit(word)` for the x, y and then z coordinate of each feature point.
+//! - `HashedPerlin` / `HashedWorley`: a 64-bit key from two words, low half first.
+//!   Each lattice cell is then hashed with the key using the SplitMix64 finaliser, and its gradient angle
+//!   or feature point is taken from the top 24 and the next 24 bits of the hash in the same way as `unit`.
+//! - `Turbulence`: the octaves of the x displacement and then the y displacement, each drawn as a `Perlin`.
+//!
+//! Here `unit(word)` is the top 24 bits of the word scaled into [0, 1), and `index(n)` is Lemire's
+//! unbiased multiply-and-reject method for drawing from `0..n`.
+//!
+//! These draws are exact on every platform, but sampling relies on the platform's floating point functions.
+//! In particular `Perlin`, `Perlin3` and `HashedPerlin` turn their angles into vectors with `cos` and `sin`,
+//! so their values can differ in the last bits between platforms.
+//!
+//! Layered noise should give each layer its own seed from [`layer_seed`],
+//! so that adding or removing a layer does not change any of the others.
+
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+/// Random number generator used by the `from_seed` constructors.
+pub(crate) fn rng(seed: u64) -> ChaCha8Rng {
+    ChaCha8Rng::seed_from_u64(seed)
+}
+
+/// Derives an independent seed for the given layer of a stack from a base seed using SplitMix64.
+pub fn layer_seed(seed: u64, layer: usize) -> u64 {
+    mix(seed.wrapping_add(
+        (layer as u64)
+            .wrapping_add(1)
+            .wrapping_mul(0x9E37_79B9_7F4A_7C15),
+    ))
+}
+
+// SplitMix64 finaliser.
+fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+// 64-bit key for hashed generators, from the low and then the high half.
+pub(crate) fn key<R: RngCore>(rng: &mut R) -> u64 {
+    let low = u64::from(rng.next_u32());
+    let high = u64::from(rng.next_u32());
+    low | (high << 32)
+}
+
+// Hash of a 2D lattice point, so that every point of an unbounded lattice gets its own value.
+pub(crate) fn hash2(key: u64, x: i64, y: i64) -> u64 {
+    let h = mix(key ^ (x as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+    mix(h ^ (y as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F))
+}
+
+// Two uniform values in [0, 1) from the top 24 bits and the next 24 bits of a hash.
+pub(crate) fn hash_units(hash: u64) -> (f32, f32) {
+    let scale = 1.0 / (1 << 24) as f32;
+    let first = (hash >> 40) as f32 * scale;
+    let second = ((hash >> 16) & 0xFF_FFFF) as f32 * scale;
+    (first, second)
+}
+
+// Uniform value in [0, 1) from the top 24 bits of the next word.
+pub(crate) fn unit<R: RngCore>(rng: &mut R) -> f32 {
+    (rng.next_u32() >> 8) as f32 * (1.0 / (1 << 24) as f32)
+}
+
+// Uniform integer in 0..bound using Lemire's multiply-and-reject method.
+pub(crate) fn index<R: RngCore>(rng: &mut R, bound: u32) -> u32 {
+    let threshold = bound.wrapping_neg() % bound;
+    loop {
+        let m = u64::from(rng.next_u32()) * u64::from(bound);
+        if m as u32 >= threshold {
+            return (m >> 32) as u32;
+        }
+    }
+}
+
+// Shuffled permutation of 0..256, repeated twice to avoid having to wrap indices.
+pub(crate) fn permutation<R: RngCore>(rng: &mut R) -> [u8; 512] {
+    let mut p = [0u8; 256];
+    for (i, val) in p.iter_mut().enumerate() {
+        *val = i as u8;
+    }
+    for i in (1..256).rev() {
+        let j = index(rng, i as u32 + 1) as usize;
+        p.swap(i, j);
+    }
+
+    let mut perm = [0u8; 512];
+    for (i, val) in perm.iter_mut().enumerate() {
+        *val = p[i & 255];
+    }
+    perm
+}
+
+// Serializes only the 256-entry permutation and rebuilds the repeated table on load.
+#[cfg(feature = "serde")]
+pub(crate) mod serde_perm {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(perm: &[u8; 512], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(&perm[..256])
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[u8; 512], D::Error> {
+        let p = Vec::<u8>::deserialize(deserializer)?;
+        if p.len() != 256 {
+            return Err(D::Error::invalid_length(
+                p.len(),
+                &"a permutation of 256 values",
+            ));
+        }
+
+        let mut seen = [false; 256];
+        for &val in &p {
+            if std::mem::replace(&mut seen[val as usize], true) {
+                return Err(D::Error::custom(format!(
+                    "duplicate value {} in permutation",
+                    val
+                )));
+            }
+        }
+
+        let mut perm = [0u8; 512];
+        for (i, val) in perm.iter_mut().enumerate() {
+            *val = p[i & 255];
+        }
+        Ok(perm)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Noise, Perlin};
+
+    #[test]
+    fn permutation_is_stable() {
+        let perm = permutation(&mut rng(0));
+        assert_eq!(perm[..8], [93, 234, 74, 207, 18, 96, 55, 129]);
+        assert_eq!(perm[256..264], perm[..8]);
+    }
+
+    #[test]
+    fn draws_are_stable() {
+        let mut stream = rng(0);
+        let scale = (1 << 24) as f32;
+        assert_eq!(unit(&mut stream) * scale, 10_983_995.0);
+        assert_eq!(unit(&mut stream) * scale, 11_896_311.0);
+
+        assert_eq!(key(&mut rng(0)), 13_080_132_717_333_068_652);
+        assert_eq!(layer_seed(0, 0), 16_294_208_416_658_607_535);
+        assert_eq!(layer_seed(42, 3), 6_349_198_060_258_255_764);
+    }
+
+    #[test]
+    fn perlin_sample_is_stable() {
+        // Within a few ulps, as the gradient vectors come from the platform's cos and sin
+        let value = Perlin::from_seed((4, 4), 1).sample(0.3, 0.4);
+        assert!((value - -0.430_500_12).abs() < 1e-6, "{}", value);
+    }
+}
@@ -1,124 +1,141 @@
-use nalgebra::Vector2;
+use nalgebra::{Matrix2, Vector2};
 use rand::Rng;
 
-use crate::noise::Noise;
+use crate::{
+    float::Float,
+    noise::{Noise, Noise64},
+    seed,
+};
 
-const SKEW_FACTOR: f32 = 0.366_025_42; // 0.5 * (sqrt(3) - 1)
-const UNSKEW_FACTOR: f32 = 0.211_324_87; // (3 - sqrt(3)) / 6
+const SKEW_FACTOR: f64 = 0.366_025_403_784_438_6; // 0.5 * (sqrt(3) - 1)
+const UNSKEW_FACTOR: f64 = 0.211_324_865_405_187_1; // (3 - sqrt(3)) / 6
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Simplex {
     scale: f32,
+    #[cfg_attr(feature = "serde", serde(with = "crate::seed::serde_perm"))]
     perm: [u8; 512], // Permutation table repeated twice
 }
 
 impl Simplex {
     pub fn new<R: Rng>(scale: f32, mut rng: R) -> Self {
-        // Generate a random permutation of 0..255
-        let mut p = [0u8; 256];
-        for (i, val) in p.iter_mut().enumerate() {
-            *val = i as u8;
-        }
-        // Shuffle the permutation
-        for i in (1..256).rev() {
-            let j = rng.random_range(0..=i);
-            p.swap(i, j);
-        }
-
-        // Duplicate it to avoid having to wrap indices
-        let mut perm = [0u8; 512];
-        for i in 0..512 {
-            perm[i] = p[i & 255];
-        }
-
+        let perm = seed::permutation(&mut rng);
         Simplex { scale, perm }
     }
 
+    /// Creates a Simplex noise generator which is reproducible from the given seed.
+    pub fn from_seed(scale: f32, seed: u64) -> Self {
+        Self::new(scale, seed::rng(seed))
+    }
+
     // Hash corner coords -> gradient index
-    fn hash(&self, x: i32, y: i32) -> usize {
+    fn hash(&self, x: i64, y: i64) -> usize {
         let idx = self.perm[(x & 255) as usize] as usize;
         self.perm[(idx + (y & 255) as usize) & 511] as usize
     }
 }
 
-impl Noise for Simplex {
-    fn sample(&self, mut x: f32, mut y: f32) -> f32 {
-        x *= self.scale;
-        y *= self.scale;
+impl Simplex {
+    // Offsets of a scaled point from the three corners of its simplex, with the gradient index of each corner.
+    fn corners<F: Float>(&self, x: F, y: F) -> [(F, F, usize); 3] {
+        let c = F::cast;
+        let unskew = c(UNSKEW_FACTOR);
 
         // Skew input space to determine which simplex cell we’re in
-        let s = (x + y) * SKEW_FACTOR;
-        let ix = (x + s).floor() as i32;
-        let iy = (y + s).floor() as i32;
+        let s = (x + y) * c(SKEW_FACTOR);
+        let ix = (x + s).floor();
+        let iy = (y + s).floor();
 
         // Unskew back
-        let t = ((ix + iy) as f32) * UNSKEW_FACTOR;
-        let x0 = x - (ix as f32 - t);
-        let y0 = y - (iy as f32 - t);
+        let t = (ix + iy) * unskew;
+        let x0 = x - (ix - t);
+        let y0 = y - (iy - t);
 
         // This determines which triangle we are in
         let (i1, j1) = if x0 > y0 { (1, 0) } else { (0, 1) };
 
         // Offsets for middle corner
-        let x1 = x0 - i1 as f32 + UNSKEW_FACTOR;
-        let y1 = y0 - j1 as f32 + UNSKEW_FACTOR;
+        let x1 = x0 - c(i1 as f64) + unskew;
+        let y1 = y0 - c(j1 as f64) + unskew;
         // Offsets for last corner
-        let x2 = x0 - 1.0 + 2.0 * UNSKEW_FACTOR;
-        let y2 = y0 - 1.0 + 2.0 * UNSKEW_FACTOR;
+        let x2 = x0 - F::one() + c(2.0) * unskew;
+        let y2 = y0 - F::one() + c(2.0) * unskew;
 
         // Calculate the hashed gradient indices of the three corners
-        let gi0 = self.hash(ix, iy);
-        let gi1 = self.hash(ix + i1, iy + j1);
-        let gi2 = self.hash(ix + 1, iy + 1);
+        let (ix, iy) = (ix.to_f64() as i64, iy.to_f64() as i64);
+        [
+            (x0, y0, self.hash(ix, iy)),
+            (x1, y1, self.hash(ix + i1, iy + j1)),
+            (x2, y2, self.hash(ix + 1, iy + 1)),
+        ]
+    }
+
+    fn sample_generic<F: Float>(&self, x: F, y: F) -> F {
+        let c = F::cast;
+        let x = x * c(self.scale as f64);
+        let y = y * c(self.scale as f64);
 
         // Calculate the contribution from the three corners
+        let [(x0, y0, gi0), (x1, y1, gi1), (x2, y2, gi2)] = self.corners(x, y);
         let n0 = corner_contrib(x0, y0, gi0);
         let n1 = corner_contrib(x1, y1, gi1);
         let n2 = corner_contrib(x2, y2, gi2);
 
         // Scale the result
-        70.0 * (n0 + n1 + n2)
+        c(70.0) * (n0 + n1 + n2)
     }
 
-    fn gradient(&self, mut x: f32, mut y: f32) -> Vector2<f32> {
-        // Scale the inputs.
-        x *= self.scale;
-        y *= self.scale;
-
-        // Skew to determine simplex cell.
-        let s = (x + y) * SKEW_FACTOR;
-        let ix = (x + s).floor() as i32;
-        let iy = (y + s).floor() as i32;
-
-        // Unskew to get offsets.
-        let t = ((ix + iy) as f32) * UNSKEW_FACTOR;
-        let x0 = x - (ix as f32 - t);
-        let y0 = y - (iy as f32 - t);
-
-        // Determine which triangle.
-        let (i1, j1) = if x0 > y0 { (1, 0) } else { (0, 1) };
+    fn gradient_generic<F: Float>(&self, x: F, y: F) -> Vector2<F> {
+        let c = F::cast;
+        let scale = c(self.scale as f64);
 
-        // Offsets for the three corners.
-        let x1 = x0 - i1 as f32 + UNSKEW_FACTOR;
-        let y1 = y0 - j1 as f32 + UNSKEW_FACTOR;
-        let x2 = x0 - 1.0 + 2.0 * UNSKEW_FACTOR;
-        let y2 = y0 - 1.0 + 2.0 * UNSKEW_FACTOR;
-
-        // Get gradient indices.
-        let gi0 = self.hash(ix, iy);
-        let gi1 = self.hash(ix + i1, iy + j1);
-        let gi2 = self.hash(ix + 1, iy + 1);
+        // Scale the inputs.
+        let x = x * scale;
+        let y = y * scale;
 
         // Compute contributions and gradients for each corner.
+        let [(x0, y0, gi0), (x1, y1, gi1), (x2, y2, gi2)] = self.corners(x, y);
         let (_n0, dx0, dy0) = corner_contrib_and_grad(x0, y0, gi0);
         let (_n1, dx1, dy1) = corner_contrib_and_grad(x1, y1, gi1);
         let (_n2, dx2, dy2) = corner_contrib_and_grad(x2, y2, gi2);
 
         // Sum up derivatives. The sample function multiplies the sum by 70.
-        let dnoise_dx = 70.0 * (dx0 + dx1 + dx2);
-        let dnoise_dy = 70.0 * (dy0 + dy1 + dy2);
+        let dnoise_dx = c(70.0) * (dx0 + dx1 + dx2);
+        let dnoise_dy = c(70.0) * (dy0 + dy1 + dy2);
 
         // Chain rule: f(x) = g(scale*x), so df/dx = scale * g'(scale*x)
-        Vector2::new(dnoise_dx * self.scale, dnoise_dy * self.scale)
+        Vector2::new(dnoise_dx * scale, dnoise_dy * scale)
+    }
+}
+
+impl Noise for Simplex {
+    fn sample(&self, x: f32, y: f32) -> f32 {
+        self.sample_generic(x, y)
+    }
+
+    fn gradient(&self, x: f32, y: f32) -> Vector2<f32> {
+        self.gradient_generic(x, y)
+    }
+
+    fn hessian(&self, x: f32, y: f32) -> Matrix2<f32> {
+        let corners = self.corners(x * self.scale, y * self.scale);
+        let sum: Matrix2<f32> = corners
+            .into_iter()
+            .map(|(x, y, gi)| corner_hessian(x, y, gi))
+            .sum();
+
+        // Each factor of scale comes from one derivative, as for the gradient
+        sum * 70.0 * self.scale * self.scale
+    }
+}
+
+impl Noise64 for Simplex {
+    fn sample_f64(&self, x: f64, y: f64) -> f64 {
+        self.sample_generic(x, y)
+    }
+
+    fn gradient_f64(&self, x: f64, y: f64) -> Vector2<f64> {
+        self.gradient_generic(x, y)
     }
 }
 
@@ -139,14 +156,14 @@ static GRAD2: [(f32, f32); 12] = [
 ];
 
 // Contribution from a corner
-fn corner_contrib(x: f32, y: f32, gi: usize) -> f32 {
-    let t = 0.5 - x * x - y * y;
-    if t < 0.0 {
-        0.0
+fn corner_contrib<F: Float>(x: F, y: F, gi: usize) -> F {
+    let t = F::cast(0.5) - x * x - y * y;
+    if t < F::zero() {
+        F::zero()
     } else {
         let (gx, gy) = GRAD2[gi % 12];
         let t2 = t * t;
-        t2 * t2 * (gx * x + gy * y)
+        t2 * t2 * (F::cast(gx as f64) * x + F::cast(gy as f64) * y)
     }
 }
 
@@ -155,19 +172,50 @@ fn corner_contrib(x: f32, y: f32, gi: usize) -> f32 {
 //   t = 0.5 - dx^2 - dy^2,
 //   contribution = t^4 * (gx * dx + gy * dy)
 // Then its partial derivatives (with respect to dx and dy) are:
-fn corner_contrib_and_grad(x: f32, y: f32, gi: usize) -> (f32, f32, f32) {
-    let t = 0.5 - x * x - y * y;
-    if t < 0.0 {
-        (0.0, 0.0, 0.0)
+fn corner_contrib_and_grad<F: Float>(x: F, y: F, gi: usize) -> (F, F, F) {
+    let t = F::cast(0.5) - x * x - y * y;
+    if t < F::zero() {
+        (F::zero(), F::zero(), F::zero())
     } else {
         let (gx, gy) = GRAD2[gi % 12];
+        let (gx, gy) = (F::cast(gx as f64), F::cast(gy as f64));
         let t2 = t * t;
         let t4 = t2 * t2;
         let dot = gx * x + gy * y;
         let contrib = t4 * dot;
         // d/dx:  d(contrib)/dx = 4*t^3*(dt/dx)*dot + t4*gx, where dt/dx = -2*x.
-        let dcontrib_dx = -8.0 * x * t * t2 * dot + t4 * gx;
-        let dcontrib_dy = -8.0 * y * t * t2 * dot + t4 * gy;
+        let dcontrib_dx = F::cast(-8.0) * x * t * t2 * dot + t4 * gx;
+        let dcontrib_dy = F::cast(-8.0) * y * t * t2 * dot + t4 * gy;
         (contrib, dcontrib_dx, dcontrib_dy)
     }
 }
+
+// Second derivatives of a corner contribution t^4 * dot, continuing from `corner_contrib_and_grad`.
+// They fall to zero at the edge of the corner's radius, so the sum over corners stays continuous.
+fn corner_hessian(x: f32, y: f32, gi: usize) -> Matrix2<f32> {
+    let t = 0.5 - x * x - y * y;
+    if t < 0.0 {
+        Matrix2::zeros()
+    } else {
+        let (gx, gy) = GRAD2[gi % 12];
+        let t2 = t * t;
+        let t3 = t2 * t;
+        let dot = gx * x + gy * y;
+        let dxx = -8.0 * t3 * dot + 48.0 * x * x * t2 * dot - 16.0 * x * t3 * gx;
+        let dyy = -8.0 * t3 * dot + 48.0 * y * y * t2 * dot - 16.0 * y * t3 * gy;
+        let dxy = 48.0 * x * y * t2 * dot - 8.0 * t3 * (x * gy + y * gx);
+        Matrix2::new(dxx, dxy, dxy, dyy)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::noise::assert_hessian_matches;
+
+    #[test]
+    fn hessian_matches_finite_differences() {
+        assert_hessian_matches(&Simplex::from_seed(4.0, 1));
+        assert_hessian_matches(&Simplex::from_seed(7.5, 2));
+    }
+}
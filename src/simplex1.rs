@@ -0,0 +1,86 @@
+use rand::Rng;
+
+use crate::{
+    float::Float,
+    lattice1::Lattice1,
+    noise::{Noise1, Noise1_64},
+    seed,
+};
+
+/// Simplex noise in one dimension, summing radial kernels around the two nearest lattice points.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct Simplex1 {
+    lattice: Lattice1,
+}
+
+impl Simplex1 {
+    pub fn new<R: Rng>(scale: f32, rng: R) -> Self {
+        Self {
+            lattice: Lattice1::new(scale, rng),
+        }
+    }
+
+    /// Creates a 1D Simplex noise generator which is reproducible from the given seed.
+    pub fn from_seed(scale: f32, seed: u64) -> Self {
+        Self::new(scale, seed::rng(seed))
+    }
+
+    /// Makes the noise repeat over the unit interval, rounding the scale to a whole number of lattice cells.
+    pub fn periodic(self) -> Self {
+        Self {
+            lattice: self.lattice.periodic(),
+        }
+    }
+}
+
+impl Simplex1 {
+    fn sample_generic<F: Float>(&self, x: F) -> F {
+        let (i, x0) = self.lattice.locate(x);
+
+        let n0 = corner_contrib_and_derivative(x0, self.lattice.value(i)).0;
+        let n1 = corner_contrib_and_derivative(x0 - F::one(), self.lattice.value(i + 1)).0;
+        F::cast(SCALE) * (n0 + n1)
+    }
+
+    fn derivative_generic<F: Float>(&self, x: F) -> F {
+        let (i, x0) = self.lattice.locate(x);
+
+        let d0 = corner_contrib_and_derivative(x0, self.lattice.value(i)).1;
+        let d1 = corner_contrib_and_derivative(x0 - F::one(), self.lattice.value(i + 1)).1;
+        // Chain rule: f(x) = g(scale*x), so df/dx = scale * g'(scale*x)
+        F::cast(SCALE) * (d0 + d1) * self.lattice.scale()
+    }
+}
+
+impl Noise1 for Simplex1 {
+    fn sample(&self, x: f32) -> f32 {
+        self.sample_generic(x)
+    }
+
+    fn derivative(&self, x: f32) -> f32 {
+        self.derivative_generic(x)
+    }
+}
+
+impl Noise1_64 for Simplex1 {
+    fn sample_f64(&self, x: f64) -> f64 {
+        self.sample_generic(x)
+    }
+
+    fn derivative_f64(&self, x: f64) -> f64 {
+        self.derivative_generic(x)
+    }
+}
+
+// Scales the sum of kernels into roughly [-1, 1]
+const SCALE: f64 = 3.16;
+
+// Contribution t^4 * g * x of a lattice point at offset x, where t = 1 - x^2,
+// and its derivative -8 * x * t^3 * g * x + t^4 * g.
+fn corner_contrib_and_derivative<F: Float>(x: F, g: F) -> (F, F) {
+    let t = F::one() - x * x;
+    let t2 = t * t;
+    let t4 = t2 * t2;
+    (t4 * g * x, F::cast(-8.0) * x * x * t * t2 * g + t4 * g)
+}
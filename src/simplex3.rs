@@ -0,0 +1,176 @@
+use nalgebra::Vector3;
+use rand::Rng;
+
+use crate::{
+    float::Float,
+    noise::{Noise3, Noise3_64},
+    seed,
+};
+
+const SKEW_FACTOR: f64 = 1.0 / 3.0;
+const UNSKEW_FACTOR: f64 = 1.0 / 6.0;
+
+/// Simplex noise in three dimensions.
+///
+/// Like the 2D [`Simplex`](crate::Simplex), this does not tile.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Simplex3 {
+    scale: f32,
+    #[cfg_attr(feature = "serde", serde(with = "crate::seed::serde_perm"))]
+    perm: [u8; 512], // Permutation table repeated twice
+}
+
+impl Simplex3 {
+    pub fn new<R: Rng>(scale: f32, mut rng: R) -> Self {
+        let perm = seed::permutation(&mut rng);
+        Simplex3 { scale, perm }
+    }
+
+    /// Creates a 3D Simplex noise generator which is reproducible from the given seed.
+    pub fn from_seed(scale: f32, seed: u64) -> Self {
+        Self::new(scale, seed::rng(seed))
+    }
+
+    // Hash corner coords -> gradient index
+    fn hash(&self, x: i32, y: i32, z: i32) -> usize {
+        let k = self.perm[(z & 255) as usize] as usize;
+        let j = self.perm[(y & 255) as usize + k] as usize;
+        self.perm[(x & 255) as usize + j] as usize
+    }
+
+    // Value and gradient together, as each corner contributes to both.
+    fn evaluate<F: Float>(&self, x: F, y: F, z: F) -> (F, Vector3<F>) {
+        let c = F::cast;
+        let scale = c(self.scale as f64);
+        let p = Vector3::new(x, y, z) * scale;
+
+        // Skew input space to determine which simplex cell we're in
+        let s = p.sum() * c(SKEW_FACTOR);
+        let cell = p.map(|c| (c + s).floor());
+
+        // Unskew back
+        let t = cell.sum() * c(UNSKEW_FACTOR);
+        let d0 = p - cell.add_scalar(-t);
+
+        // The cell is split into six tetrahedra by the ordering of the offsets
+        let (o1, o2) = if d0.x >= d0.y {
+            if d0.y >= d0.z {
+                (Vector3::new(1, 0, 0), Vector3::new(1, 1, 0))
+            } else if d0.x >= d0.z {
+                (Vector3::new(1, 0, 0), Vector3::new(1, 0, 1))
+            } else {
+                (Vector3::new(0, 0, 1), Vector3::new(1, 0, 1))
+            }
+        } else if d0.y < d0.z {
+            (Vector3::new(0, 0, 1), Vector3::new(0, 1, 1))
+        } else if d0.x < d0.z {
+            (Vector3::new(0, 1, 0), Vector3::new(0, 1, 1))
+        } else {
+            (Vector3::new(0, 1, 0), Vector3::new(1, 1, 0))
+        };
+        let corners = [Vector3::zeros(), o1, o2, Vector3::new(1, 1, 1)];
+
+        let (ix, iy, iz) = (
+            cell.x.to_f64() as i32,
+            cell.y.to_f64() as i32,
+            cell.z.to_f64() as i32,
+        );
+        let mut value = F::zero();
+        let mut gradient = Vector3::zeros();
+        for (k, corner) in corners.iter().enumerate() {
+            let offset = d0 - corner.map(|c| F::cast(c as f64))
+                + Vector3::repeat(c(k as f64) * c(UNSKEW_FACTOR));
+            let gi = self.hash(ix + corner.x, iy + corner.y, iz + corner.z);
+            let (n, dn) = corner_contrib_and_grad(offset, gi);
+            value += n;
+            gradient += dn;
+        }
+
+        // Scale the result into roughly [-1, 1]; the chain rule adds the input scale to the gradient
+        (c(76.0) * value, gradient * (c(76.0) * scale))
+    }
+}
+
+impl Noise3 for Simplex3 {
+    fn sample(&self, x: f32, y: f32, z: f32) -> f32 {
+        self.evaluate(x, y, z).0
+    }
+
+    fn gradient(&self, x: f32, y: f32, z: f32) -> Vector3<f32> {
+        self.evaluate(x, y, z).1
+    }
+}
+
+impl Noise3_64 for Simplex3 {
+    fn sample_f64(&self, x: f64, y: f64, z: f64) -> f64 {
+        self.evaluate(x, y, z).0
+    }
+
+    fn gradient_f64(&self, x: f64, y: f64, z: f64) -> Vector3<f64> {
+        self.evaluate(x, y, z).1
+    }
+}
+
+// 3D gradient table: the midpoints of the edges of a cube
+static GRAD3: [(f32, f32, f32); 12] = [
+    (1.0, 1.0, 0.0),
+    (-1.0, 1.0, 0.0),
+    (1.0, -1.0, 0.0),
+    (-1.0, -1.0, 0.0),
+    (1.0, 0.0, 1.0),
+    (-1.0, 0.0, 1.0),
+    (1.0, 0.0, -1.0),
+    (-1.0, 0.0, -1.0),
+    (0.0, 1.0, 1.0),
+    (0.0, -1.0, 1.0),
+    (0.0, 1.0, -1.0),
+    (0.0, -1.0, -1.0),
+];
+
+// Contribution t^4 * (g . d) of a corner at offset d, where t = 0.5 - |d|^2,
+// and its derivative -8 * t^3 * (g . d) * d + t^4 * g.
+// A larger radius would reach past the simplices sharing the corner, making the noise discontinuous.
+fn corner_contrib_and_grad<F: Float>(d: Vector3<F>, gi: usize) -> (F, Vector3<F>) {
+    let t = F::cast(0.5) - d.norm_squared();
+    if t < F::zero() {
+        (F::zero(), Vector3::zeros())
+    } else {
+        let (gx, gy, gz) = GRAD3[gi % 12];
+        let g = Vector3::new(gx, gy, gz).map(|c| F::cast(c as f64));
+        let t2 = t * t;
+        let t4 = t2 * t2;
+        let dot = g.dot(&d);
+        (t4 * dot, d * (F::cast(-8.0) * t * t2 * dot) + g * t4)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gradient_matches_finite_differences() {
+        let noise = Simplex3::from_seed(3.0, 7);
+        let h = 1e-3;
+        for i in 0..1000 {
+            // Points spread through a few cells without lining up with the lattice
+            let p = (Vector3::new(0.618, 0.414, 0.732) * i as f32).map(|c| c % 2.0);
+            let sample = |offset: Vector3<f32>| {
+                let q = p + offset;
+                noise.sample(q.x, q.y, q.z)
+            };
+            let difference = Vector3::from_fn(|axis, _| {
+                let step = Vector3::ith(axis, h);
+                (sample(step) - sample(-step)) / (2.0 * h)
+            });
+            let gradient = noise.gradient(p.x, p.y, p.z);
+            assert!(
+                (difference - gradient).norm() < 0.05 * gradient.norm().max(1.0),
+                "{:?}: {:?} vs {:?}",
+                p,
+                difference,
+                gradient
+            );
+        }
+    }
+}
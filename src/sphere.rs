@@ -0,0 +1,207 @@
+use std::f32::consts::{PI, TAU};
+
+use nalgebra::Vector3;
+use ndarray::Array2;
+
+use crate::noise::Noise3;
+
+/// Face of a cubemap, following the OpenGL and Vulkan layout.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CubeFace {
+    PositiveX,
+    NegativeX,
+    PositiveY,
+    NegativeY,
+    PositiveZ,
+    NegativeZ,
+}
+
+impl CubeFace {
+    /// All six faces in the usual layer order.
+    pub const ALL: [CubeFace; 6] = [
+        CubeFace::PositiveX,
+        CubeFace::NegativeX,
+        CubeFace::PositiveY,
+        CubeFace::NegativeY,
+        CubeFace::PositiveZ,
+        CubeFace::NegativeZ,
+    ];
+
+    /// Direction through a point on the face, where `s` runs from -1 to 1 along a row and `t` from -1 to 1 down a column.
+    ///
+    /// The direction is not normalised.
+    pub fn direction(self, s: f32, t: f32) -> Vector3<f32> {
+        match self {
+            CubeFace::PositiveX => Vector3::new(1.0, -t, -s),
+            CubeFace::NegativeX => Vector3::new(-1.0, -t, s),
+            CubeFace::PositiveY => Vector3::new(s, 1.0, t),
+            CubeFace::NegativeY => Vector3::new(s, -1.0, -t),
+            CubeFace::PositiveZ => Vector3::new(s, -t, 1.0),
+            CubeFace::NegativeZ => Vector3::new(-s, -t, -1.0),
+        }
+    }
+}
+
+/// Placement of the texels on each face of a cubemap.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CubeGrid {
+    /// Texels sample their centres, at `(2k + 1) / resolution - 1`, as a GPU samples a cubemap.
+    #[default]
+    TexelCentres,
+    /// The outer texels lie exactly on the cube's edges, at `(2k - (resolution - 1)) / (resolution - 1)`,
+    /// and texels shared with a neighbouring face are computed from bit-identical directions,
+    /// so adjacent faces hold exactly the same values along their edges.
+    SharedEdges,
+}
+
+impl CubeGrid {
+    // Face coordinate in [-1, 1] of texel k.
+    fn coordinate(self, k: usize, resolution: usize) -> f32 {
+        match self {
+            CubeGrid::TexelCentres => (2.0 * k as f32 + 1.0) / resolution as f32 - 1.0,
+            CubeGrid::SharedEdges => {
+                // Symmetric about the centre, so that k and resolution - 1 - k give exactly opposite coordinates
+                let last = resolution.saturating_sub(1);
+                (2.0 * k as f32 - last as f32) / last.max(1) as f32
+            }
+        }
+    }
+}
+
+/// Noise on the unit sphere, evaluated from a 3D noise at each direction,
+/// so unlike a unit-square noise wrapped around a sphere it has no seam and no pinching at the poles.
+///
+/// Direction `d` is sampled at `0.5 + radius * d`. With a radius of at most 0.5 the sphere stays within a single period
+/// of noise tileable over the unit cube, such as [`Perlin3`](crate::Perlin3), so the pattern never repeats on the surface.
+pub struct Sphere<N = Box<dyn Noise3>> {
+    noise: N,
+    radius: f32,
+}
+
+impl<N: Noise3> Sphere<N> {
+    pub fn new(noise: N, radius: f32) -> Self {
+        Self { noise, radius }
+    }
+
+    fn point(&self, direction: &Vector3<f32>) -> Vector3<f32> {
+        Vector3::repeat(0.5) + direction * self.radius
+    }
+
+    /// Value in the given direction, which does not need to be normalised.
+    pub fn sample(&self, direction: Vector3<f32>) -> f32 {
+        let p = self.point(&direction.normalize());
+        self.noise.sample(p.x, p.y, p.z)
+    }
+
+    /// Gradient along the surface per unit of arc length,
+    /// which is the gradient of the 3D noise projected into the tangent plane at the direction.
+    pub fn gradient(&self, direction: Vector3<f32>) -> Vector3<f32> {
+        let d = direction.normalize();
+        let p = self.point(&d);
+        // Chain rule: the sphere is scaled by the radius
+        let g = self.noise.gradient(p.x, p.y, p.z) * self.radius;
+        g - d * g.dot(&d)
+    }
+
+    /// Samples an equirectangular map of `(height, width)` pixels with +z at the top.
+    ///
+    /// Row `i` is at the polar angle `PI * (i + 0.5) / height`, so no row lies on a pole,
+    /// and column `j` at the longitude `TAU * j / width`, so the map tiles horizontally like [`sample_grid`](crate::sample_grid).
+    pub fn equirectangular(&self, resolution: (usize, usize)) -> Array2<f32> {
+        let (height, width) = resolution;
+        Array2::from_shape_fn(resolution, |(i, j)| {
+            let polar = PI * (i as f32 + 0.5) / height as f32;
+            let longitude = TAU * j as f32 / width as f32;
+            let (sin_polar, cos_polar) = polar.sin_cos();
+            let (sin_longitude, cos_longitude) = longitude.sin_cos();
+            self.sample(Vector3::new(
+                sin_polar * cos_longitude,
+                sin_polar * sin_longitude,
+                cos_polar,
+            ))
+        })
+    }
+
+    /// Samples one face of a cubemap at the centres of `resolution` by `resolution` texels.
+    pub fn cube_face(&self, face: CubeFace, resolution: usize) -> Array2<f32> {
+        self.cube_face_on(face, CubeGrid::TexelCentres, resolution)
+    }
+
+    /// Samples one face of a cubemap on the given grid of `resolution` by `resolution` texels.
+    pub fn cube_face_on(&self, face: CubeFace, grid: CubeGrid, resolution: usize) -> Array2<f32> {
+        face_grid(face, grid, resolution, |direction| self.sample(direction))
+    }
+
+    /// Samples all six faces of a cubemap on the given grid, in the order of [`CubeFace::ALL`].
+    pub fn cubemap(&self, grid: CubeGrid, resolution: usize) -> [Array2<f32>; 6] {
+        CubeFace::ALL.map(|face| self.cube_face_on(face, grid, resolution))
+    }
+
+    /// Object-space normals of the surface displaced to a radius of `1 + height_scale * value`,
+    /// on the same grids as [`cubemap`](Sphere::cubemap) and computed directly from the analytic gradient.
+    ///
+    /// Unlike tangent-space normals these do not depend on the face, so on [`CubeGrid::SharedEdges`]
+    /// they also match exactly along the edges.
+    pub fn cubemap_normals(
+        &self,
+        height_scale: f32,
+        grid: CubeGrid,
+        resolution: usize,
+    ) -> [Array2<Vector3<f32>>; 6] {
+        CubeFace::ALL.map(|face| {
+            face_grid(face, grid, resolution, |direction| {
+                let d = direction.normalize();
+                // The surface moves outwards along d, and tilts against the gradient along the surface
+                let radius = 1.0 + height_scale * self.sample(d);
+                (d * radius - self.gradient(d) * height_scale).normalize()
+            })
+        })
+    }
+}
+
+// Evaluates a function at the direction of each texel of a face.
+fn face_grid<T, F: Fn(Vector3<f32>) -> T>(
+    face: CubeFace,
+    grid: CubeGrid,
+    resolution: usize,
+    f: F,
+) -> Array2<T> {
+    Array2::from_shape_fn((resolution, resolution), |(i, j)| {
+        f(face.direction(
+            grid.coordinate(j, resolution),
+            grid.coordinate(i, resolution),
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::Perlin3;
+
+    #[test]
+    fn shared_edges_match_across_faces() {
+        let sphere = Sphere::new(Perlin3::from_seed((6, 6, 6), 2), 0.5);
+        let resolution = 5;
+        let faces = sphere.cubemap(CubeGrid::SharedEdges, resolution);
+
+        // Texels on an edge are seen by two faces, and corners by three
+        let mut seen = HashMap::new();
+        let mut shared = 0;
+        for (face, values) in CubeFace::ALL.iter().zip(&faces) {
+            for ((i, j), value) in values.indexed_iter() {
+                let s = CubeGrid::SharedEdges.coordinate(j, resolution);
+                let t = CubeGrid::SharedEdges.coordinate(i, resolution);
+                // Adding zero turns -0.0 into 0.0, so both give the same key
+                let key = face.direction(s, t).map(|c| (c + 0.0).to_bits());
+                if let Some(previous) = seen.insert(key, *value) {
+                    assert_eq!(previous.to_bits(), value.to_bits(), "{:?}", key);
+                    shared += 1;
+                }
+            }
+        }
+        assert_eq!(shared, 12 * (resolution - 2) + 8 * 2);
+    }
+}
@@ -1,7 +1,14 @@
-use nalgebra::Vector2;
+use std::ops::{AddAssign, Mul};
 
-use crate::noise::Noise;
+use nalgebra::{Matrix2, Vector2, Vector3};
 
+use crate::{
+    float::Float,
+    noise::{Noise, Noise1, Noise1_64, Noise3, Noise3_64, Noise64},
+};
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum GradientFunction {
     Noop,
     Inverse { factor: f32 },   // 1/(1 + factor * x)
@@ -14,25 +21,49 @@ pub enum GradientFunction {
 }
 
 impl GradientFunction {
-    fn scale(&self, x: f32) -> f32 {
-        match self {
-            GradientFunction::Noop => 1.0,
-            GradientFunction::Inverse { factor } => 1.0 / (1.0 + factor * x),
-            GradientFunction::Exp { scale } => (-(scale * x).powi(2)).exp(),
-            GradientFunction::Sigmoid { factor } => 1.0 / (1.0 + (-factor * x).exp()),
-            GradientFunction::Tanh { factor } => (f32::tanh(factor * x) + 1.0) / 2.0,
-            GradientFunction::Cosine { frequency } => 0.5 * ((frequency * x).cos() + 1.0),
-            GradientFunction::Quadratic { factor } => 1.0 / (1.0 + factor * x.powi(2)),
+    fn scale<F: Float>(&self, x: F) -> F {
+        let c = |value: f32| F::cast(value as f64);
+        let half = F::cast(0.5);
+        match *self {
+            GradientFunction::Noop => F::one(),
+            GradientFunction::Inverse { factor } => F::one() / (F::one() + c(factor) * x),
+            GradientFunction::Exp { scale } => (-(c(scale) * x).powi(2)).exp(),
+            GradientFunction::Sigmoid { factor } => F::one() / (F::one() + (-c(factor) * x).exp()),
+            GradientFunction::Tanh { factor } => ((c(factor) * x).tanh() + F::one()) / F::cast(2.0),
+            GradientFunction::Cosine { frequency } => half * ((c(frequency) * x).cos() + F::one()),
+            GradientFunction::Quadratic { factor } => F::one() / (F::one() + c(factor) * x.powi(2)),
+            GradientFunction::Arctan { factor } => (c(factor) * x).atan() / F::pi() + half,
+        }
+    }
+
+    // Derivative of `scale` with respect to x.
+    fn scale_derivative(&self, x: f32) -> f32 {
+        match *self {
+            GradientFunction::Noop => 0.0,
+            GradientFunction::Inverse { factor } => -factor / (1.0 + factor * x).powi(2),
+            GradientFunction::Exp { scale } => {
+                -2.0 * scale * scale * x * (-(scale * x).powi(2)).exp()
+            }
+            GradientFunction::Sigmoid { factor } => {
+                let sigmoid = self.scale(x);
+                factor * sigmoid * (1.0 - sigmoid)
+            }
+            GradientFunction::Tanh { factor } => factor * (1.0 - (factor * x).tanh().powi(2)) / 2.0,
+            GradientFunction::Cosine { frequency } => -0.5 * frequency * (frequency * x).sin(),
+            GradientFunction::Quadratic { factor } => {
+                -2.0 * factor * x / (1.0 + factor * x * x).powi(2)
+            }
             GradientFunction::Arctan { factor } => {
-                f32::atan(factor * x) / std::f32::consts::PI + 0.5
+                factor / (std::f32::consts::PI * (1.0 + (factor * x).powi(2)))
             }
         }
     }
 }
 
-pub struct Stack {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Stack<N = Box<dyn Noise>> {
     gradient_function: GradientFunction,
-    noise_weights: Vec<(Box<dyn Noise>, f32)>,
+    noise_weights: Vec<(N, f32)>,
 }
 
 impl Stack {
@@ -40,6 +71,15 @@ impl Stack {
         gradient_function: GradientFunction,
         noise_weights: Vec<(Box<dyn Noise>, f32)>,
     ) -> Self {
+        Self::from_layers(gradient_function, noise_weights)
+    }
+}
+
+impl<N> Stack<N> {
+    /// Creates a stack of any one layer type, such as [`Generator`](crate::Generator) or `Box<dyn Noise64>`.
+    ///
+    /// Unlike [`Stack::new`] the layer type is inferred from the layers, so mixed generators must already share a type.
+    pub fn from_layers(gradient_function: GradientFunction, noise_weights: Vec<(N, f32)>) -> Self {
         debug_assert!(noise_weights.iter().all(|(_, weight)| *weight >= 0.0));
 
         Self {
@@ -49,27 +89,346 @@ impl Stack {
     }
 }
 
-impl Noise for Stack {
-    fn sample(&self, x: f32, y: f32) -> f32 {
-        let mut total_sample = 0.0;
-        let mut total_gradient = Vector2::new(0.0, 0.0);
-        for (noise, weight) in &self.noise_weights {
-            let factor = self.gradient_function.scale(total_gradient.norm());
-            let sample_value = noise.sample(x, y) * weight;
-            let grad_value = *weight * noise.gradient(x, y);
-            total_sample += sample_value * factor;
-            total_gradient += grad_value * factor;
+// Gradient of a layer in any number of dimensions, which only needs adding up and measuring to be combined.
+trait Slope<F>: Copy + AddAssign + Mul<F, Output = Self> {
+    fn zero() -> Self;
+    fn magnitude(&self) -> F;
+}
+
+impl<F: Float> Slope<F> for Vector2<F> {
+    fn zero() -> Self {
+        Self::zeros()
+    }
+
+    fn magnitude(&self) -> F {
+        self.norm()
+    }
+}
+
+impl<F: Float> Slope<F> for Vector3<F> {
+    fn zero() -> Self {
+        Self::zeros()
+    }
+
+    fn magnitude(&self) -> F {
+        self.norm()
+    }
+}
+
+// A 1D layer has a scalar derivative, measured by its absolute value.
+impl<F: Float> Slope<F> for F {
+    fn zero() -> Self {
+        F::zero()
+    }
+
+    fn magnitude(&self) -> F {
+        self.abs()
+    }
+}
+
+// Weighted sum of the layers, where each weight is scaled by the gradient of the layers before it.
+// Layer values are only sampled when `sample` is given, as the gradient does not depend on them.
+fn combine<N, F: Float, G: Slope<F>>(
+    gradient_function: &GradientFunction,
+    noise_weights: &[(N, f32)],
+    sample: Option<&dyn Fn(&N) -> F>,
+    gradient: &dyn Fn(&N) -> G,
+) -> (F, G) {
+    let mut total_sample = F::zero();
+    let mut total_gradient = G::zero();
+    for (noise, weight) in noise_weights {
+        let weight = F::cast(*weight as f64);
+        let factor = gradient_function.scale(total_gradient.magnitude());
+        if let Some(sample) = sample {
+            total_sample += sample(noise) * weight * factor;
         }
-        total_sample
+        total_gradient += gradient(noise) * weight * factor;
+    }
+    (total_sample, total_gradient)
+}
+
+impl<N> Stack<N> {
+    fn combine<F: Float, G: Slope<F>>(
+        &self,
+        sample: Option<&dyn Fn(&N) -> F>,
+        gradient: &dyn Fn(&N) -> G,
+    ) -> (F, G) {
+        combine(
+            &self.gradient_function,
+            &self.noise_weights,
+            sample,
+            gradient,
+        )
+    }
+}
+
+impl<N: Noise> Noise for Stack<N> {
+    fn sample(&self, x: f32, y: f32) -> f32 {
+        self.combine(Some(&|noise: &N| noise.sample(x, y)), &|noise: &N| {
+            noise.gradient(x, y)
+        })
+        .0
     }
 
     fn gradient(&self, x: f32, y: f32) -> Vector2<f32> {
-        let mut total_gradient = Vector2::new(0.0, 0.0);
+        self.combine(None, &|noise: &N| noise.gradient(x, y)).1
+    }
+
+    // Derivative of the combined gradient, including the change in each layer's factor.
+    fn hessian(&self, x: f32, y: f32) -> Matrix2<f32> {
+        let mut total_gradient = Vector2::zeros();
+        let mut total_hessian = Matrix2::zeros();
         for (noise, weight) in &self.noise_weights {
-            let factor = self.gradient_function.scale(total_gradient.norm());
-            let grad_value = *weight * noise.gradient(x, y);
-            total_gradient += grad_value * factor;
+            let norm = total_gradient.norm();
+            let factor = self.gradient_function.scale(norm);
+            let factor_gradient = if norm > 0.0 {
+                let slope = self.gradient_function.scale_derivative(norm);
+                total_hessian.transpose() * total_gradient * (slope / norm)
+            } else {
+                Vector2::zeros()
+            };
+            let gradient = noise.gradient(x, y);
+            total_hessian +=
+                (noise.hessian(x, y) * factor + gradient * factor_gradient.transpose()) * *weight;
+            total_gradient += gradient * *weight * factor;
+        }
+        // The weighted gradient need not be the gradient of any function, so keep the symmetric part
+        (total_hessian + total_hessian.transpose()) * 0.5
+    }
+}
+
+impl<N: Noise64> Noise64 for Stack<N> {
+    fn sample_f64(&self, x: f64, y: f64) -> f64 {
+        self.combine(Some(&|noise: &N| noise.sample_f64(x, y)), &|noise: &N| {
+            noise.gradient_f64(x, y)
+        })
+        .0
+    }
+
+    fn gradient_f64(&self, x: f64, y: f64) -> Vector2<f64> {
+        self.combine(None, &|noise: &N| noise.gradient_f64(x, y)).1
+    }
+}
+
+/// Weighted stack of 3D noise functions, combined in the same way as [`Stack`].
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Stack3<N = Box<dyn Noise3>> {
+    gradient_function: GradientFunction,
+    noise_weights: Vec<(N, f32)>,
+}
+
+impl Stack3 {
+    pub fn new(
+        gradient_function: GradientFunction,
+        noise_weights: Vec<(Box<dyn Noise3>, f32)>,
+    ) -> Self {
+        Self::from_layers(gradient_function, noise_weights)
+    }
+}
+
+impl<N> Stack3<N> {
+    /// Creates a stack of any one layer type, as [`Stack::from_layers`] does.
+    pub fn from_layers(gradient_function: GradientFunction, noise_weights: Vec<(N, f32)>) -> Self {
+        debug_assert!(noise_weights.iter().all(|(_, weight)| *weight >= 0.0));
+
+        Self {
+            gradient_function,
+            noise_weights,
+        }
+    }
+}
+
+impl<N> Stack3<N> {
+    fn combine<F: Float, G: Slope<F>>(
+        &self,
+        sample: Option<&dyn Fn(&N) -> F>,
+        gradient: &dyn Fn(&N) -> G,
+    ) -> (F, G) {
+        combine(
+            &self.gradient_function,
+            &self.noise_weights,
+            sample,
+            gradient,
+        )
+    }
+}
+
+impl<N: Noise3> Noise3 for Stack3<N> {
+    fn sample(&self, x: f32, y: f32, z: f32) -> f32 {
+        self.combine(Some(&|noise: &N| noise.sample(x, y, z)), &|noise: &N| {
+            noise.gradient(x, y, z)
+        })
+        .0
+    }
+
+    fn gradient(&self, x: f32, y: f32, z: f32) -> Vector3<f32> {
+        self.combine(None, &|noise: &N| noise.gradient(x, y, z)).1
+    }
+}
+
+impl<N: Noise3_64> Noise3_64 for Stack3<N> {
+    fn sample_f64(&self, x: f64, y: f64, z: f64) -> f64 {
+        self.combine(
+            Some(&|noise: &N| noise.sample_f64(x, y, z)),
+            &|noise: &N| noise.gradient_f64(x, y, z),
+        )
+        .0
+    }
+
+    fn gradient_f64(&self, x: f64, y: f64, z: f64) -> Vector3<f64> {
+        self.combine(None, &|noise: &N| noise.gradient_f64(x, y, z))
+            .1
+    }
+}
+
+/// Weighted stack of 1D noise functions, combined in the same way as [`Stack`].
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Stack1<N = Box<dyn Noise1>> {
+    gradient_function: GradientFunction,
+    noise_weights: Vec<(N, f32)>,
+}
+
+impl Stack1 {
+    pub fn new(
+        gradient_function: GradientFunction,
+        noise_weights: Vec<(Box<dyn Noise1>, f32)>,
+    ) -> Self {
+        Self::from_layers(gradient_function, noise_weights)
+    }
+}
+
+impl<N> Stack1<N> {
+    /// Creates a stack of any one layer type, as [`Stack::from_layers`] does.
+    pub fn from_layers(gradient_function: GradientFunction, noise_weights: Vec<(N, f32)>) -> Self {
+        debug_assert!(noise_weights.iter().all(|(_, weight)| *weight >= 0.0));
+
+        Self {
+            gradient_function,
+            noise_weights,
+        }
+    }
+}
+
+impl<N> Stack1<N> {
+    fn combine<F: Float>(
+        &self,
+        sample: Option<&dyn Fn(&N) -> F>,
+        derivative: &dyn Fn(&N) -> F,
+    ) -> (F, F) {
+        combine(
+            &self.gradient_function,
+            &self.noise_weights,
+            sample,
+            derivative,
+        )
+    }
+}
+
+impl<N: Noise1> Noise1 for Stack1<N> {
+    fn sample(&self, x: f32) -> f32 {
+        self.combine(Some(&|noise: &N| noise.sample(x)), &|noise: &N| {
+            noise.derivative(x)
+        })
+        .0
+    }
+
+    fn derivative(&self, x: f32) -> f32 {
+        self.combine(None, &|noise: &N| noise.derivative(x)).1
+    }
+}
+
+impl<N: Noise1_64> Noise1_64 for Stack1<N> {
+    fn sample_f64(&self, x: f64) -> f64 {
+        self.combine(Some(&|noise: &N| noise.sample_f64(x)), &|noise: &N| {
+            noise.derivative_f64(x)
+        })
+        .0
+    }
+
+    fn derivative_f64(&self, x: f64) -> f64 {
+        self.combine(None, &|noise: &N| noise.derivative_f64(x)).1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{noise::assert_hessian_matches, Perlin, Perlin3, Simplex1};
+
+    fn layers() -> Vec<(Box<dyn Noise>, f32)> {
+        vec![
+            (Box::new(Perlin::from_seed((2, 2), 1)), 1.0),
+            (Box::new(Perlin::from_seed((4, 4), 2)), 0.5),
+            (Box::new(Perlin::from_seed((8, 8), 3)), 0.25),
+        ]
+    }
+
+    #[test]
+    fn hessian_matches_finite_differences() {
+        assert_hessian_matches(&Stack::new(GradientFunction::Noop, layers()));
+        assert_hessian_matches(&Stack::new(GradientFunction::Exp { scale: 0.2 }, layers()));
+        assert_hessian_matches(&Stack::new(
+            GradientFunction::Inverse { factor: 0.5 },
+            layers(),
+        ));
+        assert_hessian_matches(&Stack::new(
+            GradientFunction::Sigmoid { factor: 0.3 },
+            layers(),
+        ));
+        assert_hessian_matches(&Stack::new(
+            GradientFunction::Tanh { factor: 0.3 },
+            layers(),
+        ));
+        assert_hessian_matches(&Stack::new(
+            GradientFunction::Cosine { frequency: 0.3 },
+            layers(),
+        ));
+        assert_hessian_matches(&Stack::new(
+            GradientFunction::Quadratic { factor: 0.1 },
+            layers(),
+        ));
+        assert_hessian_matches(&Stack::new(
+            GradientFunction::Arctan { factor: 0.3 },
+            layers(),
+        ));
+    }
+
+    #[test]
+    fn f64_matches_f32() {
+        let function = GradientFunction::Exp { scale: 0.2 };
+        let stack3 = Stack3::from_layers(
+            function.clone(),
+            vec![
+                (Perlin3::from_seed((2, 2, 2), 1), 1.0),
+                (Perlin3::from_seed((4, 4, 4), 2), 0.5),
+            ],
+        );
+        let stack1 = Stack1::from_layers(
+            function,
+            vec![
+                (Simplex1::from_seed(2.0, 1), 1.0),
+                (Simplex1::from_seed(4.0, 2), 0.5),
+            ],
+        );
+        for i in 0..100 {
+            let (x, y, z) = (
+                (0.618_034 * i as f32) % 1.0,
+                (0.414_214 * i as f32) % 1.0,
+                (0.732_051 * i as f32) % 1.0,
+            );
+            let (x64, y64, z64) = (x as f64, y as f64, z as f64);
+
+            let sample = stack3.sample(x, y, z) as f64;
+            assert!((stack3.sample_f64(x64, y64, z64) - sample).abs() < 1e-4);
+            let gradient = stack3.gradient(x, y, z).cast::<f64>();
+            let error = (stack3.gradient_f64(x64, y64, z64) - gradient).norm();
+            assert!(error < 1e-3 * gradient.norm().max(1.0));
+
+            assert!((stack1.sample_f64(x64) - stack1.sample(x) as f64).abs() < 1e-4);
+            let derivative = stack1.derivative(x) as f64;
+            assert!(
+                (stack1.derivative_f64(x64) - derivative).abs() < 1e-3 * derivative.abs().max(1.0)
+            );
         }
-        total_gradient
     }
 }
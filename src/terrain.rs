@@ -0,0 +1,144 @@
+use std::f32::consts::TAU;
+
+use ndarray::Array2;
+
+use crate::noise::Noise;
+
+/// Local shape of the surface `height_scale * noise(u, v)` at a single point.
+///
+/// North is up the image (decreasing `v`) and east is increasing `u`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TerrainAttributes {
+    /// Angle of the surface from horizontal, in radians.
+    pub slope: f32,
+    /// Compass bearing of the direction of steepest descent, in radians clockwise from north.
+    /// This is NaN where the surface is flat.
+    pub aspect: f32,
+    /// Curvature along the direction of steepest descent. Positive values are convex.
+    pub profile_curvature: f32,
+    /// Curvature of the contour line through the point. Positive values are convex.
+    pub plan_curvature: f32,
+}
+
+impl TerrainAttributes {
+    /// Computes the terrain attributes from the gradient and Hessian of the noise.
+    pub fn at<N: Noise + ?Sized>(noise: &N, height_scale: f32, u: f32, v: f32) -> Self {
+        let gradient = noise.gradient(u, v) * height_scale;
+        let hessian = noise.hessian(u, v) * height_scale;
+
+        let (p, q) = (gradient.x, gradient.y);
+        let (r, s, t) = (hessian[(0, 0)], hessian[(0, 1)], hessian[(1, 1)]);
+        let p2q2 = p * p + q * q;
+
+        let slope = p2q2.sqrt().atan();
+        if p2q2 == 0.0 {
+            return Self {
+                slope,
+                aspect: f32::NAN,
+                profile_curvature: 0.0,
+                plan_curvature: 0.0,
+            };
+        }
+
+        // Steepest descent is along -gradient; north is -v
+        let aspect = (-p).atan2(q).rem_euclid(TAU);
+        let profile_curvature =
+            -(p * p * r + 2.0 * p * q * s + q * q * t) / (p2q2 * (1.0 + p2q2).powf(1.5));
+        let plan_curvature = -(q * q * r - 2.0 * p * q * s + p * p * t) / p2q2.powf(1.5);
+
+        Self {
+            slope,
+            aspect,
+            profile_curvature,
+            plan_curvature,
+        }
+    }
+}
+
+/// Terrain attributes of a noise function sampled on the same grid as [`sample_grid`](crate::sample_grid).
+pub struct TerrainMaps {
+    pub slope: Array2<f32>,
+    pub aspect: Array2<f32>,
+    pub profile_curvature: Array2<f32>,
+    pub plan_curvature: Array2<f32>,
+}
+
+impl TerrainMaps {
+    pub fn new<N: Noise + ?Sized>(
+        noise: &N,
+        height_scale: f32,
+        resolution: (usize, usize),
+    ) -> Self {
+        let (height, width) = resolution;
+        let attributes = Array2::from_shape_fn(resolution, |(i, j)| {
+            let u = j as f32 / width as f32;
+            let v = i as f32 / height as f32;
+            TerrainAttributes::at(noise, height_scale, u, v)
+        });
+
+        Self {
+            slope: attributes.mapv(|a| a.slope),
+            aspect: attributes.mapv(|a| a.aspect),
+            profile_curvature: attributes.mapv(|a| a.profile_curvature),
+            plan_curvature: attributes.mapv(|a| a.plan_curvature),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vector2;
+
+    use super::*;
+
+    // z = a u^2 + b v^2 + c u + d v, whose central difference Hessian is exact up to rounding.
+    struct Quadratic {
+        a: f32,
+        b: f32,
+        c: f32,
+        d: f32,
+    }
+
+    impl Noise for Quadratic {
+        fn sample(&self, u: f32, v: f32) -> f32 {
+            self.a * u * u + self.b * v * v + self.c * u + self.d * v
+        }
+
+        fn gradient(&self, u: f32, v: f32) -> Vector2<f32> {
+            Vector2::new(2.0 * self.a * u + self.c, 2.0 * self.b * v + self.d)
+        }
+    }
+
+    #[test]
+    fn curvature_of_quadratic_surfaces() {
+        // A valley running along v: contours bend uphill, and the slope is straight
+        let valley = Quadratic {
+            a: 1.0,
+            b: 0.0,
+            c: 0.0,
+            d: 1.0,
+        };
+        let attributes = TerrainAttributes::at(&valley, 1.0, 0.0, 0.3);
+        assert!((attributes.slope - std::f32::consts::FRAC_PI_4).abs() < 1e-5);
+        assert!(attributes.profile_curvature.abs() < 1e-3);
+        assert!((attributes.plan_curvature + 2.0).abs() < 1e-3);
+
+        // A ridge of height v - v^2: the slope rolls over and the contours are straight.
+        // With q = 0.5 and t = -2, profile curvature is 2 * 0.25 / (0.25 * 1.25^1.5).
+        let ridge = Quadratic {
+            a: 0.0,
+            b: -1.0,
+            c: 0.0,
+            d: 1.0,
+        };
+        let attributes = TerrainAttributes::at(&ridge, 1.0, 0.5, 0.25);
+        let expected = 2.0 / 1.25_f32.powf(1.5);
+        assert!((attributes.profile_curvature - expected).abs() < 1e-3);
+        assert!(attributes.plan_curvature.abs() < 1e-3);
+
+        // Here plan curvature is -r / q, which does not change with the height scale
+        let attributes = TerrainAttributes::at(&valley, 2.0, 0.0, 0.3);
+        assert!(attributes.profile_curvature.abs() < 1e-3);
+        assert!((attributes.plan_curvature + 2.0).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,142 @@
+use std::f32::consts::SQRT_2;
+
+use ndarray::Array2;
+
+use crate::boundary::Boundary;
+
+/// Thermal weathering, where material slides downhill wherever the slope exceeds the talus angle.
+///
+/// Each iteration moves material from every cell to its lower neighbours among the eight surrounding it,
+/// in proportion to how far each exceeds the talus slope. All cells are updated together,
+/// so the result does not depend on the order they are visited in, and the total height is conserved.
+#[derive(Clone, Debug)]
+pub struct ThermalErosion {
+    /// Steepest stable slope, in radians.
+    pub talus_angle: f32,
+    /// Fraction of the excess height moved at each iteration, in [0, 1].
+    pub rate: f32,
+    pub iterations: usize,
+    /// Horizontal distance between neighbouring cells, in height units.
+    /// For `height_scale * noise` sampled over the unit square this is `1 / width`.
+    pub cell_size: f32,
+    pub boundary: Boundary,
+}
+
+impl Default for ThermalErosion {
+    fn default() -> Self {
+        Self {
+            talus_angle: 30_f32.to_radians(),
+            rate: 0.5,
+            iterations: 50,
+            cell_size: 1.0,
+            boundary: Boundary::Clamped,
+        }
+    }
+}
+
+impl ThermalErosion {
+    /// Erodes a heightmap in place.
+    pub fn erode(&self, heights: &mut Array2<f32>) {
+        const NEIGHBOURS: [((isize, isize), f32); 8] = [
+            ((-1, -1), SQRT_2),
+            ((-1, 0), 1.0),
+            ((-1, 1), SQRT_2),
+            ((0, -1), 1.0),
+            ((0, 1), 1.0),
+            ((1, -1), SQRT_2),
+            ((1, 0), 1.0),
+            ((1, 1), SQRT_2),
+        ];
+
+        let dim = heights.dim();
+        let talus = self.talus_angle.tan() * self.cell_size;
+        let mut change = Array2::zeros(dim);
+        let mut excess = [(None, 0.0); 8];
+
+        for _ in 0..self.iterations {
+            change.fill(0.0);
+
+            for ((i, j), &height) in heights.indexed_iter() {
+                let mut total = 0.0;
+                let mut largest: f32 = 0.0;
+                for (slot, &(offset, distance)) in excess.iter_mut().zip(&NEIGHBOURS) {
+                    *slot = (None, 0.0);
+                    if let Some(cell) = self.boundary.neighbour(dim, (i, j), offset) {
+                        let over = height - heights[cell] - talus * distance;
+                        if over > 0.0 {
+                            *slot = (Some(cell), over);
+                            total += over;
+                            largest = largest.max(over);
+                        }
+                    }
+                }
+                if total == 0.0 {
+                    continue;
+                }
+
+                // Moving half the largest excess at most levels the steepest pair without overshooting
+                let moved = 0.5 * self.rate * largest;
+                change[(i, j)] -= moved;
+                for &(cell, over) in &excess {
+                    if let Some(cell) = cell {
+                        change[cell] += moved * over / total;
+                    }
+                }
+            }
+
+            *heights += &change;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{sample_grid, Perlin};
+
+    fn heights() -> Array2<f32> {
+        sample_grid(&Perlin::from_seed((4, 4), 1), (48, 48))
+    }
+
+    fn erosion(boundary: Boundary) -> ThermalErosion {
+        ThermalErosion {
+            talus_angle: 0.5_f32.to_radians(),
+            cell_size: 1.0 / 48.0,
+            boundary,
+            ..Default::default()
+        }
+    }
+
+    // Shifts the grid by (di, dj) cells, wrapping around the edges.
+    fn roll(heights: &Array2<f32>, (di, dj): (usize, usize)) -> Array2<f32> {
+        let (rows, columns) = heights.dim();
+        Array2::from_shape_fn((rows, columns), |(i, j)| {
+            heights[((i + rows - di) % rows, (j + columns - dj) % columns)]
+        })
+    }
+
+    #[test]
+    fn total_height_is_conserved() {
+        for boundary in [Boundary::Clamped, Boundary::Periodic] {
+            let mut eroded = heights();
+            erosion(boundary).erode(&mut eroded);
+            assert!((&eroded - &heights()).mapv(f32::abs).sum() > 1.0);
+
+            let total = |heights: &Array2<f32>| heights.iter().map(|&h| h as f64).sum::<f64>();
+            assert!((total(&eroded) - total(&heights())).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn periodic_erosion_wraps_around_the_edges() {
+        // On a torus no cell is special, so shifting the input only shifts the output
+        let shift = (17, 30);
+        let mut eroded = heights();
+        erosion(Boundary::Periodic).erode(&mut eroded);
+        let mut shifted = roll(&heights(), shift);
+        erosion(Boundary::Periodic).erode(&mut shifted);
+
+        let difference = (&roll(&eroded, shift) - &shifted).mapv(f32::abs);
+        assert!(difference.iter().all(|&d| d < 1e-5));
+    }
+}
@@ -0,0 +1,298 @@
+use nalgebra::{Matrix2, Vector2};
+
+use crate::{
+    float::Float,
+    noise::{Noise, Noise64, NoiseAt},
+};
+
+/// Shifts the domain of a noise function, sampling it at `p + offset`.
+///
+/// Translation always preserves tiling.
+pub struct Translate<N = Box<dyn Noise>> {
+    noise: N,
+    offset: Vector2<f32>,
+}
+
+impl<N> Translate<N> {
+    pub fn new(noise: N, offset: Vector2<f32>) -> Self {
+        Self { noise, offset }
+    }
+
+    fn transform<F: Float>(&self, x: F, y: F) -> Vector2<F> {
+        Vector2::new(x, y) + self.offset.map(|c| F::cast(c as f64))
+    }
+
+    fn sample_generic<F: Float>(&self, x: F, y: F) -> F
+    where
+        N: NoiseAt<F>,
+    {
+        let p = self.transform(x, y);
+        self.noise.sample_at(p.x, p.y)
+    }
+
+    fn gradient_generic<F: Float>(&self, x: F, y: F) -> Vector2<F>
+    where
+        N: NoiseAt<F>,
+    {
+        let p = self.transform(x, y);
+        self.noise.gradient_at(p.x, p.y)
+    }
+}
+
+impl<N: Noise> Noise for Translate<N> {
+    fn sample(&self, x: f32, y: f32) -> f32 {
+        self.sample_generic(x, y)
+    }
+
+    fn gradient(&self, x: f32, y: f32) -> Vector2<f32> {
+        self.gradient_generic(x, y)
+    }
+
+    fn hessian(&self, x: f32, y: f32) -> Matrix2<f32> {
+        let p = self.transform(x, y);
+        self.noise.hessian(p.x, p.y)
+    }
+}
+
+impl<N: Noise64> Noise64 for Translate<N> {
+    fn sample_f64(&self, x: f64, y: f64) -> f64 {
+        self.sample_generic(x, y)
+    }
+
+    fn gradient_f64(&self, x: f64, y: f64) -> Vector2<f64> {
+        self.gradient_generic(x, y)
+    }
+}
+
+/// Rotates the domain of a noise function about the origin.
+pub struct Rotate<N = Box<dyn Noise>> {
+    affine: Affine2<N>,
+}
+
+impl<N> Rotate<N> {
+    pub fn new(noise: N, angle: f32) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        Self {
+            affine: Affine2::new(noise, Matrix2::new(cos, -sin, sin, cos), Vector2::zeros()),
+        }
+    }
+
+    /// Rotates the domain onto the direction of the integer lattice vector `(a, b)`.
+    ///
+    /// The domain is also scaled by the length of the vector, which keeps the result tileable on the unit square.
+    pub fn tileable(noise: N, (a, b): (i32, i32)) -> Self {
+        assert!(a != 0 || b != 0);
+
+        Self {
+            affine: Affine2::tileable(noise, Matrix2::new(a, -b, b, a), Vector2::zeros()),
+        }
+    }
+}
+
+impl<N: Noise> Noise for Rotate<N> {
+    fn sample(&self, x: f32, y: f32) -> f32 {
+        self.affine.sample(x, y)
+    }
+
+    fn gradient(&self, x: f32, y: f32) -> Vector2<f32> {
+        self.affine.gradient(x, y)
+    }
+
+    fn hessian(&self, x: f32, y: f32) -> Matrix2<f32> {
+        self.affine.hessian(x, y)
+    }
+}
+
+impl<N: Noise64> Noise64 for Rotate<N> {
+    fn sample_f64(&self, x: f64, y: f64) -> f64 {
+        self.affine.sample_f64(x, y)
+    }
+
+    fn gradient_f64(&self, x: f64, y: f64) -> Vector2<f64> {
+        self.affine.gradient_f64(x, y)
+    }
+}
+
+/// Scales the domain of a noise function independently along each axis.
+pub struct ScaleDomain<N = Box<dyn Noise>> {
+    noise: N,
+    scale: Vector2<f32>,
+}
+
+impl<N> ScaleDomain<N> {
+    pub fn new(noise: N, scale: Vector2<f32>) -> Self {
+        Self { noise, scale }
+    }
+
+    /// Scales the domain by integer factors, which keeps the result tileable on the unit square.
+    pub fn tileable(noise: N, (sx, sy): (i32, i32)) -> Self {
+        assert!(sx != 0 && sy != 0);
+
+        Self::new(noise, Vector2::new(sx as f32, sy as f32))
+    }
+
+    fn scale<F: Float>(&self) -> Vector2<F> {
+        self.scale.map(|c| F::cast(c as f64))
+    }
+
+    fn sample_generic<F: Float>(&self, x: F, y: F) -> F
+    where
+        N: NoiseAt<F>,
+    {
+        let scale = self.scale();
+        self.noise.sample_at(x * scale.x, y * scale.y)
+    }
+
+    fn gradient_generic<F: Float>(&self, x: F, y: F) -> Vector2<F>
+    where
+        N: NoiseAt<F>,
+    {
+        let scale = self.scale();
+        self.noise
+            .gradient_at(x * scale.x, y * scale.y)
+            .component_mul(&scale)
+    }
+}
+
+impl<N: Noise> Noise for ScaleDomain<N> {
+    fn sample(&self, x: f32, y: f32) -> f32 {
+        self.sample_generic(x, y)
+    }
+
+    fn gradient(&self, x: f32, y: f32) -> Vector2<f32> {
+        self.gradient_generic(x, y)
+    }
+
+    fn hessian(&self, x: f32, y: f32) -> Matrix2<f32> {
+        let scale = Matrix2::from_diagonal(&self.scale);
+        scale * self.noise.hessian(x * self.scale.x, y * self.scale.y) * scale
+    }
+}
+
+impl<N: Noise64> Noise64 for ScaleDomain<N> {
+    fn sample_f64(&self, x: f64, y: f64) -> f64 {
+        self.sample_generic(x, y)
+    }
+
+    fn gradient_f64(&self, x: f64, y: f64) -> Vector2<f64> {
+        self.gradient_generic(x, y)
+    }
+}
+
+/// General affine transform of the domain of a noise function, sampling it at `matrix * p + translation`.
+///
+/// The returned gradient is mapped back into the input frame by the transpose of the matrix.
+pub struct Affine2<N = Box<dyn Noise>> {
+    noise: N,
+    matrix: Matrix2<f32>,
+    translation: Vector2<f32>,
+}
+
+impl<N> Affine2<N> {
+    pub fn new(noise: N, matrix: Matrix2<f32>, translation: Vector2<f32>) -> Self {
+        Self {
+            noise,
+            matrix,
+            translation,
+        }
+    }
+
+    /// Creates an affine transform from an integer matrix.
+    ///
+    /// An integer matrix maps the unit lattice onto itself, so a noise that tiles the unit square still tiles afterwards.
+    pub fn tileable(noise: N, matrix: Matrix2<i32>, translation: Vector2<f32>) -> Self {
+        Self::new(noise, matrix.map(|m| m as f32), translation)
+    }
+
+    fn matrix<F: Float>(&self) -> Matrix2<F> {
+        self.matrix.map(|m| F::cast(m as f64))
+    }
+
+    fn transform<F: Float>(&self, x: F, y: F) -> Vector2<F> {
+        self.matrix() * Vector2::new(x, y) + self.translation.map(|t| F::cast(t as f64))
+    }
+
+    fn sample_generic<F: Float>(&self, x: F, y: F) -> F
+    where
+        N: NoiseAt<F>,
+    {
+        let p = self.transform(x, y);
+        self.noise.sample_at(p.x, p.y)
+    }
+
+    fn gradient_generic<F: Float>(&self, x: F, y: F) -> Vector2<F>
+    where
+        N: NoiseAt<F>,
+    {
+        let p = self.transform(x, y);
+        self.matrix().transpose() * self.noise.gradient_at(p.x, p.y)
+    }
+}
+
+impl<N: Noise> Noise for Affine2<N> {
+    fn sample(&self, x: f32, y: f32) -> f32 {
+        self.sample_generic(x, y)
+    }
+
+    fn gradient(&self, x: f32, y: f32) -> Vector2<f32> {
+        self.gradient_generic(x, y)
+    }
+
+    fn hessian(&self, x: f32, y: f32) -> Matrix2<f32> {
+        let p = self.transform(x, y);
+        self.matrix.transpose() * self.noise.hessian(p.x, p.y) * self.matrix
+    }
+}
+
+impl<N: Noise64> Noise64 for Affine2<N> {
+    fn sample_f64(&self, x: f64, y: f64) -> f64 {
+        self.sample_generic(x, y)
+    }
+
+    fn gradient_f64(&self, x: f64, y: f64) -> Vector2<f64> {
+        self.gradient_generic(x, y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        noise::{assert_f64_matches, assert_gradient_matches, assert_hessian_matches},
+        Perlin,
+    };
+
+    fn perlin() -> Box<dyn Noise> {
+        Box::new(Perlin::from_seed((4, 4), 1))
+    }
+
+    #[test]
+    fn gradients_match_finite_differences() {
+        assert_gradient_matches(&Translate::new(perlin(), Vector2::new(0.3, -0.7)));
+        assert_gradient_matches(&Rotate::new(perlin(), 0.7));
+        assert_gradient_matches(&Rotate::tileable(perlin(), (1, 2)));
+        assert_gradient_matches(&ScaleDomain::new(perlin(), Vector2::new(2.0, 0.5)));
+        let matrix = Matrix2::new(1.5, -0.4, 0.3, 0.8);
+        assert_gradient_matches(&Affine2::new(perlin(), matrix, Vector2::new(0.2, 0.1)));
+    }
+
+    #[test]
+    fn hessians_match_finite_differences() {
+        assert_hessian_matches(&Translate::new(perlin(), Vector2::new(0.3, -0.7)));
+        assert_hessian_matches(&Rotate::new(perlin(), 0.7));
+        assert_hessian_matches(&ScaleDomain::new(perlin(), Vector2::new(2.0, 0.5)));
+        let matrix = Matrix2::new(1.5, -0.4, 0.3, 0.8);
+        assert_hessian_matches(&Affine2::new(perlin(), matrix, Vector2::new(0.2, 0.1)));
+    }
+
+    #[test]
+    fn f64_matches_f32() {
+        let perlin = || Perlin::from_seed((4, 4), 1);
+        assert_f64_matches(&Translate::new(perlin(), Vector2::new(0.3, -0.7)));
+        assert_f64_matches(&Rotate::new(perlin(), 0.7));
+        assert_f64_matches(&Rotate::tileable(perlin(), (1, 2)));
+        assert_f64_matches(&ScaleDomain::new(perlin(), Vector2::new(2.0, 0.5)));
+        let matrix = Matrix2::new(1.5, -0.4, 0.3, 0.8);
+        assert_f64_matches(&Affine2::new(perlin(), matrix, Vector2::new(0.2, 0.1)));
+    }
+}
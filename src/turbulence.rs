@@ -0,0 +1,152 @@
+use nalgebra::{Matrix2, Vector2};
+use rand::Rng;
+
+use crate::{
+    float::Float,
+    noise::{Noise, Noise64, NoiseAt},
+    perlin::Perlin,
+    seed,
+    stack::{GradientFunction, Stack},
+};
+
+/// Randomly displaces the domain of a noise function, sampling it at `p + power * d(p)`.
+///
+/// Each displacement component `d` is a fractal sum of `roughness` Perlin octaves,
+/// starting at a lattice of `frequency` cells and doubling with each octave.
+/// Because the lattices are integer-sized the displacement is periodic, so a tileable noise stays tileable.
+pub struct Turbulence<N = Box<dyn Noise>> {
+    noise: N,
+    power: f32,
+    x_distort: Stack<Perlin>,
+    y_distort: Stack<Perlin>,
+}
+
+impl Turbulence {
+    /// Largest lattice allowed for the finest octave, which bounds the memory used by the displacement.
+    pub const MAX_LATTICE: usize = 1024;
+}
+
+impl<N> Turbulence<N> {
+    /// Panics if `roughness` or `frequency` is zero,
+    /// or if the finest octave's lattice of `frequency << (roughness - 1)` cells exceeds [`MAX_LATTICE`](Turbulence::MAX_LATTICE).
+    pub fn new<R: Rng>(
+        noise: N,
+        power: f32,
+        roughness: usize,
+        frequency: usize,
+        mut rng: R,
+    ) -> Self {
+        assert!(roughness > 0);
+        assert!(frequency > 0);
+        assert!(
+            finest_lattice(roughness, frequency)
+                .is_some_and(|cells| cells <= Turbulence::MAX_LATTICE),
+            "turbulence lattice exceeds {} cells",
+            Turbulence::MAX_LATTICE
+        );
+
+        let x_distort = distortion(roughness, frequency, &mut rng);
+        let y_distort = distortion(roughness, frequency, &mut rng);
+
+        Self {
+            noise,
+            power,
+            x_distort,
+            y_distort,
+        }
+    }
+
+    /// Creates a turbulence modifier whose displacement is reproducible from the given seed.
+    pub fn from_seed(noise: N, power: f32, roughness: usize, frequency: usize, seed: u64) -> Self {
+        Self::new(noise, power, roughness, frequency, seed::rng(seed))
+    }
+
+    fn displace<F: Float>(&self, x: F, y: F) -> Vector2<F>
+    where
+        Stack<Perlin>: NoiseAt<F>,
+    {
+        let power = F::cast(self.power as f64);
+        Vector2::new(
+            x + power * self.x_distort.sample_at(x, y),
+            y + power * self.y_distort.sample_at(x, y),
+        )
+    }
+
+    fn sample_generic<F: Float>(&self, x: F, y: F) -> F
+    where
+        N: NoiseAt<F>,
+        Stack<Perlin>: NoiseAt<F>,
+    {
+        let p = self.displace(x, y);
+        self.noise.sample_at(p.x, p.y)
+    }
+
+    fn gradient_generic<F: Float>(&self, x: F, y: F) -> Vector2<F>
+    where
+        N: NoiseAt<F>,
+        Stack<Perlin>: NoiseAt<F>,
+    {
+        let p = self.displace(x, y);
+
+        // Jacobian of the displaced point, J = I + power * [grad(dx); grad(dy)]
+        let gx = self.x_distort.gradient_at(x, y);
+        let gy = self.y_distort.gradient_at(x, y);
+        let jacobian =
+            Matrix2::identity() + Matrix2::new(gx.x, gx.y, gy.x, gy.y) * F::cast(self.power as f64);
+
+        jacobian.transpose() * self.noise.gradient_at(p.x, p.y)
+    }
+}
+
+impl<N: Noise> Noise for Turbulence<N> {
+    fn sample(&self, x: f32, y: f32) -> f32 {
+        self.sample_generic(x, y)
+    }
+
+    fn gradient(&self, x: f32, y: f32) -> Vector2<f32> {
+        self.gradient_generic(x, y)
+    }
+}
+
+impl<N: Noise64> Noise64 for Turbulence<N> {
+    fn sample_f64(&self, x: f64, y: f64) -> f64 {
+        self.sample_generic(x, y)
+    }
+
+    fn gradient_f64(&self, x: f64, y: f64) -> Vector2<f64> {
+        self.gradient_generic(x, y)
+    }
+}
+
+// Lattice size of the finest octave, or None if it overflows.
+pub(crate) fn finest_lattice(roughness: usize, frequency: usize) -> Option<usize> {
+    let octaves = u32::try_from(roughness.checked_sub(1)?).ok()?;
+    frequency.checked_mul(1_usize.checked_shl(octaves)?)
+}
+
+// Fractal sum of Perlin octaves, each with twice the frequency and half the weight of the last.
+fn distortion<R: Rng>(roughness: usize, frequency: usize, mut rng: R) -> Stack<Perlin> {
+    let octaves = (0..roughness)
+        .map(|octave| {
+            let cells = frequency << octave;
+            (
+                Perlin::new((cells, cells), &mut rng),
+                0.5_f32.powi(octave as i32),
+            )
+        })
+        .collect();
+    Stack::from_layers(GradientFunction::Noop, octaves)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::noise::{assert_f64_matches, assert_gradient_matches};
+
+    #[test]
+    fn f64_matches_f32() {
+        let noise = Turbulence::from_seed(Perlin::from_seed((4, 4), 1), 0.1, 3, 2, 1);
+        assert_gradient_matches(&noise);
+        assert_f64_matches(&noise);
+    }
+}
@@ -0,0 +1,71 @@
+use rand::Rng;
+
+use crate::{
+    float::Float,
+    lattice1::Lattice1,
+    noise::{Noise1, Noise1_64},
+    perlin::{fade, fade_derivative, lerp},
+    seed,
+};
+
+/// Value noise in one dimension, smoothly interpolating random values at integer lattice points.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct Value1 {
+    lattice: Lattice1,
+}
+
+impl Value1 {
+    pub fn new<R: Rng>(scale: f32, rng: R) -> Self {
+        Self {
+            lattice: Lattice1::new(scale, rng),
+        }
+    }
+
+    /// Creates a 1D value noise generator which is reproducible from the given seed.
+    pub fn from_seed(scale: f32, seed: u64) -> Self {
+        Self::new(scale, seed::rng(seed))
+    }
+
+    /// Makes the noise repeat over the unit interval, rounding the scale to a whole number of lattice cells.
+    pub fn periodic(self) -> Self {
+        Self {
+            lattice: self.lattice.periodic(),
+        }
+    }
+}
+
+impl Value1 {
+    fn sample_generic<F: Float>(&self, x: F) -> F {
+        let (i, xf) = self.lattice.locate(x);
+        lerp(self.lattice.value(i), self.lattice.value(i + 1), fade(xf))
+    }
+
+    fn derivative_generic<F: Float>(&self, x: F) -> F {
+        let (i, xf) = self.lattice.locate(x);
+        // Chain rule: f(x) = g(scale*x), so df/dx = scale * g'(scale*x)
+        fade_derivative(xf)
+            * (self.lattice.value::<F>(i + 1) - self.lattice.value(i))
+            * self.lattice.scale()
+    }
+}
+
+impl Noise1 for Value1 {
+    fn sample(&self, x: f32) -> f32 {
+        self.sample_generic(x)
+    }
+
+    fn derivative(&self, x: f32) -> f32 {
+        self.derivative_generic(x)
+    }
+}
+
+impl Noise1_64 for Value1 {
+    fn sample_f64(&self, x: f64) -> f64 {
+        self.sample_generic(x)
+    }
+
+    fn derivative_f64(&self, x: f64) -> f64 {
+        self.derivative_generic(x)
+    }
+}
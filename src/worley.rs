@@ -1,10 +1,13 @@
-use std::f32;
-
 use nalgebra::Vector2;
 use rand::Rng;
 
-use crate::noise::Noise;
+use crate::{
+    float::Float,
+    noise::{Noise, Noise64},
+    seed,
+};
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Worley {
     points: Vec<Vector2<f32>>,
 }
@@ -13,42 +16,76 @@ impl Worley {
     /// Creates a new Worley noise generator with the specified number of feature points.
     pub fn new<R: Rng>(num_points: usize, mut rng: R) -> Self {
         let points = (0..num_points)
-            .map(|_| Vector2::new(rng.random::<f32>(), rng.random::<f32>()))
+            .map(|_| {
+                let x = seed::unit(&mut rng);
+                let y = seed::unit(&mut rng);
+                Vector2::new(x, y)
+            })
             .collect();
         Self { points }
     }
+
+    /// Creates a Worley noise generator which is reproducible from the given seed.
+    pub fn from_seed(num_points: usize, seed: u64) -> Self {
+        Self::new(num_points, seed::rng(seed))
+    }
 }
 
-impl Noise for Worley {
+impl Worley {
     /// Samples the Worley noise at a given (x, y) coordinate.
     /// Ensures the noise is tilable within the unit square.
-    fn sample(&self, x: f32, y: f32) -> f32 {
+    fn sample_generic<F: Float>(&self, x: F, y: F) -> F {
         let sample_point = Vector2::new(x, y);
+        let (zero, one) = (F::zero(), F::one());
         self.points
             .iter()
-            .flat_map(|&p| {
+            .flat_map(|p| {
+                let p = p.map(|c| F::cast(c as f64));
                 // Wrap points to make the noise tilable
                 [
                     p,
-                    p + Vector2::new(1.0, 0.0),
-                    p + Vector2::new(0.0, 1.0),
-                    p + Vector2::new(-1.0, 0.0),
-                    p + Vector2::new(0.0, -1.0),
-                    p + Vector2::new(1.0, 1.0),
-                    p + Vector2::new(-1.0, 1.0),
-                    p + Vector2::new(1.0, -1.0),
-                    p + Vector2::new(-1.0, -1.0),
+                    p + Vector2::new(one, zero),
+                    p + Vector2::new(zero, one),
+                    p + Vector2::new(-one, zero),
+                    p + Vector2::new(zero, -one),
+                    p + Vector2::new(one, one),
+                    p + Vector2::new(-one, one),
+                    p + Vector2::new(one, -one),
+                    p + Vector2::new(-one, -one),
                 ]
             })
             .map(|p| (p - sample_point).norm())
-            .fold(f32::MAX, f32::min)
+            .fold(F::max_value().unwrap(), F::min)
     }
 
     /// Computes the numerical gradient of the Worley noise at a given point.
-    fn gradient(&self, x: f32, y: f32) -> Vector2<f32> {
-        let epsilon = 4e-3;
-        let dx = (self.sample(x + epsilon, y) - self.sample(x - epsilon, y)) / (2.0 * epsilon);
-        let dy = (self.sample(x, y + epsilon) - self.sample(x, y - epsilon)) / (2.0 * epsilon);
+    fn gradient_generic<F: Float>(&self, x: F, y: F) -> Vector2<F> {
+        let epsilon = F::cast(4e-3);
+        let two = F::cast(2.0);
+        let dx = (self.sample_generic(x + epsilon, y) - self.sample_generic(x - epsilon, y))
+            / (two * epsilon);
+        let dy = (self.sample_generic(x, y + epsilon) - self.sample_generic(x, y - epsilon))
+            / (two * epsilon);
         Vector2::new(dx, dy)
     }
 }
+
+impl Noise for Worley {
+    fn sample(&self, x: f32, y: f32) -> f32 {
+        self.sample_generic(x, y)
+    }
+
+    fn gradient(&self, x: f32, y: f32) -> Vector2<f32> {
+        self.gradient_generic(x, y)
+    }
+}
+
+impl Noise64 for Worley {
+    fn sample_f64(&self, x: f64, y: f64) -> f64 {
+        self.sample_generic(x, y)
+    }
+
+    fn gradient_f64(&self, x: f64, y: f64) -> Vector2<f64> {
+        self.gradient_generic(x, y)
+    }
+}
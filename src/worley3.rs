@@ -0,0 +1,92 @@
+use nalgebra::Vector3;
+use rand::Rng;
+
+use crate::{
+    float::Float,
+    noise::{Noise3, Noise3_64},
+    seed,
+};
+
+/// Worley noise in three dimensions, tileable over the unit cube.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Worley3 {
+    points: Vec<Vector3<f32>>,
+}
+
+impl Worley3 {
+    /// Creates a new 3D Worley noise generator with the specified number of feature points.
+    pub fn new<R: Rng>(num_points: usize, mut rng: R) -> Self {
+        let points = (0..num_points)
+            .map(|_| {
+                let x = seed::unit(&mut rng);
+                let y = seed::unit(&mut rng);
+                let z = seed::unit(&mut rng);
+                Vector3::new(x, y, z)
+            })
+            .collect();
+        Self { points }
+    }
+
+    /// Creates a 3D Worley noise generator which is reproducible from the given seed.
+    pub fn from_seed(num_points: usize, seed: u64) -> Self {
+        Self::new(num_points, seed::rng(seed))
+    }
+
+    // Offset from the nearest feature point, including its copies in the 26 neighbouring cubes.
+    fn nearest<F: Float>(&self, x: F, y: F, z: F) -> Vector3<F> {
+        // Wrapping the sample point into the unit cube makes the noise periodic everywhere
+        let one = F::one();
+        let sample_point = Vector3::new(x, y, z).map(|c| c - c.floor());
+        self.points
+            .iter()
+            .flat_map(|p| {
+                let p = p.map(|c| F::cast(c as f64));
+                (0..27).map(move |k| {
+                    let shift =
+                        Vector3::new(k % 3, (k / 3) % 3, k / 9).map(|c| F::cast(c as f64) - one);
+                    sample_point - (p + shift)
+                })
+            })
+            .min_by(|a, b| {
+                a.norm_squared()
+                    .partial_cmp(&b.norm_squared())
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
+            .unwrap_or(Vector3::new(F::max_value().unwrap(), F::zero(), F::zero()))
+    }
+
+    // The distance grows fastest directly away from the nearest feature point,
+    // so the gradient is the unit vector pointing away from it.
+    fn gradient_generic<F: Float>(&self, x: F, y: F, z: F) -> Vector3<F> {
+        let offset = self.nearest(x, y, z);
+        let distance = offset.norm();
+        if distance > F::zero() {
+            offset / distance
+        } else {
+            Vector3::zeros()
+        }
+    }
+}
+
+impl Noise3 for Worley3 {
+    /// Distance from the nearest feature point.
+    fn sample(&self, x: f32, y: f32, z: f32) -> f32 {
+        self.nearest(x, y, z).norm()
+    }
+
+    /// The unit vector pointing away from the nearest feature point.
+    /// This is exact everywhere except on the boundaries between cells, where the nearest point changes.
+    fn gradient(&self, x: f32, y: f32, z: f32) -> Vector3<f32> {
+        self.gradient_generic(x, y, z)
+    }
+}
+
+impl Noise3_64 for Worley3 {
+    fn sample_f64(&self, x: f64, y: f64, z: f64) -> f64 {
+        self.nearest(x, y, z).norm()
+    }
+
+    fn gradient_f64(&self, x: f64, y: f64, z: f64) -> Vector3<f64> {
+        self.gradient_generic(x, y, z)
+    }
+}